@@ -0,0 +1,594 @@
+use crate::text::{cells, display_width};
+
+/// Convenience function for converting a bitstring to a 6dot braille unicode character (brc: braille char)
+///
+/// # Arguments
+///
+/// - `i` - Bitstring, representing the dots of the braille character as below:
+///   ```text
+///   1  4 | 1  4
+///   2  5 | 2  5
+///   3  6 | 3  6
+///        | 7  8
+///   ```
+///   If this only supported the 6dot, it could have used u8
+///   the brr function is useful for mapping a sensible way to use dot8, to the actual format
+pub fn brc(i: u32) -> char {
+    const BRAILLE_UNICODE_OFFSET: u32 = 0x2800;
+    if i == 0 {
+        ' '
+    } else if i <= 255 {
+        std::char::from_u32(BRAILLE_UNICODE_OFFSET + i).unwrap()
+    } else {
+        ' '
+    }
+}
+
+/// Map sensible braille mapping to legacy (actual) mapping (brr : braille real)
+fn brr(i: u32, btype: &BrailleType) -> u32 {
+    match btype {
+        BrailleType::Dot6 => i,
+        // Keep bits 0, 1, 2 and 7, move bit 3 to bit 6, and left shift bits 4, 5 and 6
+        BrailleType::Dot8 => (i & 135) | ((i & 8) << 3) | ((i & 112) >> 1),
+    }
+}
+
+/// Quadrant block characters, indexed by a bitstring of the quadrants that are filled,
+/// see blr for the order of the bits
+const QUADRANTS: [char; 16] = [
+    ' ', '▘', '▖', '▌', '▝', '▀', '▞', '▛', '▗', '▚', '▄', '▙', '▐', '▜', '▟', '█',
+];
+
+/// Convenience function for converting a bitstring to a quadrant block character (blc: block char)
+///
+/// # Arguments
+///
+/// - `i` - Bitstring, representing the filled quadrants of the character as below:
+///   ```text
+///   1  3
+///   2  4
+///   ```
+pub fn blc(i: u32) -> char {
+    QUADRANTS.get(i as usize).cloned().unwrap_or(' ')
+}
+
+/// Map the quadrants of a character to the bitstring used by blc (blr: block real).
+/// Half blocks have a single column of quadrants, which is used for both columns.
+fn blr(i: u32, btype: &BlockType) -> u32 {
+    match btype {
+        BlockType::Half => (i & 3) | ((i & 3) << 2),
+        BlockType::Quadrant => i,
+    }
+}
+
+/// The content of a cell of a Canvas
+#[derive(Clone, PartialEq)]
+pub enum Pixel {
+    /// A single character
+    Char(char),
+    /// A braille character, with the dots of the bitstring, see brc
+    Braille(u32),
+    /// A block character, with the quadrants of the bitstring, see blc
+    Block(u32),
+    /// The text of one column, see text::cells
+    Text(String),
+}
+
+impl std::fmt::Display for Pixel {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                Pixel::Char(c) => format!("{}", c),
+                Pixel::Braille(i) => format!("{}", brc(*i)),
+                Pixel::Block(i) => format!("{}", blc(*i)),
+                Pixel::Text(text) => text.clone(),
+            }
+        )
+    }
+}
+
+/// A cell of a Canvas, with the color it is written in
+#[derive(Clone, Default, PartialEq)]
+pub enum GraphPixel<T> {
+    Normal(T),
+    Green(T),
+    Blue(T),
+    Red(T),
+    Yellow(T),
+    Magenta(T),
+    Cyan(T),
+    /// Drawn faint, for things in the background such as gridlines
+    Dim(T),
+    /// Nothing is drawn in the cell, it is written as a space
+    #[default]
+    Blank,
+}
+
+impl<T> GraphPixel<T> {
+    /// The pixel, regardless of color, or None for a blank pixel
+    pub fn inner(&self) -> Option<&T> {
+        match self {
+            GraphPixel::Normal(px)
+            | GraphPixel::Green(px)
+            | GraphPixel::Blue(px)
+            | GraphPixel::Red(px)
+            | GraphPixel::Yellow(px)
+            | GraphPixel::Magenta(px)
+            | GraphPixel::Cyan(px)
+            | GraphPixel::Dim(px) => Some(px),
+            GraphPixel::Blank => None,
+        }
+    }
+}
+
+impl<T: std::fmt::Display> std::fmt::Display for GraphPixel<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                GraphPixel::Normal(c) => format!("{}", c),
+
+                #[cfg(feature = "ansi")]
+                GraphPixel::Green(c) => format!("\x1b[32m{}\x1b[0m", c),
+                #[cfg(feature = "ansi")]
+                GraphPixel::Blue(c) => format!("\x1b[34m{}\x1b[0m", c),
+                #[cfg(feature = "ansi")]
+                GraphPixel::Red(c) => format!("\x1b[31m{}\x1b[0m", c),
+                #[cfg(feature = "ansi")]
+                GraphPixel::Yellow(c) => format!("\x1b[33m{}\x1b[0m", c),
+                #[cfg(feature = "ansi")]
+                GraphPixel::Magenta(c) => format!("\x1b[35m{}\x1b[0m", c),
+                #[cfg(feature = "ansi")]
+                GraphPixel::Cyan(c) => format!("\x1b[36m{}\x1b[0m", c),
+                #[cfg(feature = "ansi")]
+                GraphPixel::Dim(c) => format!("\x1b[2m{}\x1b[0m", c),
+
+                #[cfg(not(feature = "ansi"))]
+                GraphPixel::Green(c) => format!("{}", c),
+                #[cfg(not(feature = "ansi"))]
+                GraphPixel::Blue(c) => format!("{}", c),
+                #[cfg(not(feature = "ansi"))]
+                GraphPixel::Red(c) => format!("{}", c),
+                #[cfg(not(feature = "ansi"))]
+                GraphPixel::Yellow(c) => format!("{}", c),
+                #[cfg(not(feature = "ansi"))]
+                GraphPixel::Magenta(c) => format!("{}", c),
+                #[cfg(not(feature = "ansi"))]
+                GraphPixel::Cyan(c) => format!("{}", c),
+                #[cfg(not(feature = "ansi"))]
+                GraphPixel::Dim(c) => format!("{}", c),
+
+                GraphPixel::Blank => String::from(" "),
+            }
+        )
+    }
+}
+
+#[derive(PartialEq, Clone)]
+pub enum BrailleType {
+    Dot6,
+    Dot8,
+}
+
+/// The block characters used by GraphType::Blocks
+#[derive(PartialEq, Clone)]
+pub enum BlockType {
+    /// Upper and lower half blocks, with two rows in each character
+    Half,
+    /// Quadrant blocks, with two columns and two rows in each character
+    Quadrant,
+}
+
+/// The grid of dots inside each character, for drawing with a resolution finer than
+/// a character
+#[derive(Clone)]
+pub enum SubCell {
+    Braille(BrailleType),
+    Block(BlockType),
+}
+
+impl SubCell {
+    /// Number of dots in each row of a character
+    pub fn columns(&self) -> usize {
+        match self {
+            SubCell::Braille(_) | SubCell::Block(BlockType::Quadrant) => 2,
+            SubCell::Block(BlockType::Half) => 1,
+        }
+    }
+
+    /// Number of dots in each column of a character
+    pub fn rows(&self) -> usize {
+        match self {
+            SubCell::Braille(BrailleType::Dot6) => 3,
+            SubCell::Braille(BrailleType::Dot8) => 4,
+            SubCell::Block(_) => 2,
+        }
+    }
+
+    /// The bits of the dot at (x, y) inside a character
+    fn bits(&self, x: usize, y: usize) -> u32 {
+        let i = 1 << (y + x * self.rows());
+        match self {
+            SubCell::Braille(btype) => brr(i, btype),
+            SubCell::Block(btype) => blr(i, btype),
+        }
+    }
+
+    /// The bits of the dots already set in `px`, if it is drawn with this grid
+    fn dots(&self, px: Option<&Pixel>) -> u32 {
+        match (self, px) {
+            (SubCell::Braille(_), Some(Pixel::Braille(i))) => *i,
+            (SubCell::Block(_), Some(Pixel::Block(i))) => *i,
+            _ => 0,
+        }
+    }
+
+    /// A pixel with the dots of `bits`
+    fn pixel(&self, bits: u32) -> Pixel {
+        match self {
+            SubCell::Braille(_) => Pixel::Braille(bits),
+            SubCell::Block(_) => Pixel::Block(bits),
+        }
+    }
+}
+
+/// A grid of cells to draw on, with primitives for drawing points, lines, rectangles and text.
+/// Positions are (x, y) with (0, 0) the top left cell, or the top left dot for the methods
+/// drawing dots, see SubCell. Anything drawn outside the canvas is left out.
+#[derive(Clone, Default)]
+pub struct Canvas {
+    /// The cells, row by row
+    cells: Vec<GraphPixel<Pixel>>,
+    /// Number of columns
+    width: usize,
+    /// Number of rows
+    height: usize,
+}
+
+impl Canvas {
+    /// Create a blank canvas
+    ///
+    /// # Arguments
+    ///
+    /// * `width` - Number of columns
+    /// * `height` - Number of rows
+    pub fn new(width: usize, height: usize) -> Self {
+        Canvas {
+            cells: vec![GraphPixel::default(); width * height],
+            width,
+            height,
+        }
+    }
+
+    /// Number of columns of the canvas
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    /// Number of rows of the canvas
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    /// The cell at (x, y), or None if it is outside the canvas
+    pub fn get(&self, x: usize, y: usize) -> Option<&GraphPixel<Pixel>> {
+        if x >= self.width || y >= self.height {
+            return None;
+        }
+        self.cells.get(y * self.width + x)
+    }
+
+    /// Set the cell at (x, y)
+    ///
+    /// # Arguments
+    ///
+    /// * `x` - Column of the cell
+    /// * `y` - Row of the cell
+    /// * `px` - The new content of the cell
+    pub fn set(&mut self, x: usize, y: usize, px: GraphPixel<Pixel>) {
+        if x >= self.width || y >= self.height {
+            return;
+        }
+        self.cells[y * self.width + x] = px;
+    }
+
+    /// Set a single dot, dots already set in the same cell are kept.
+    /// A cell that does not hold dots of the same grid is replaced.
+    ///
+    /// # Arguments
+    ///
+    /// * `x` - Column of the dot, there are grid.columns() dots in each cell
+    /// * `y` - Row of the dot, there are grid.rows() dots in each cell
+    /// * `grid` - The dots inside each cell
+    /// * `color` - The GraphPixel variant to draw the cell with, such as GraphPixel::Red
+    pub fn dot(
+        &mut self,
+        x: usize,
+        y: usize,
+        grid: &SubCell,
+        color: fn(Pixel) -> GraphPixel<Pixel>,
+    ) {
+        let (cell_x, cell_y) = (x / grid.columns(), y / grid.rows());
+        let Some(px) = self.get(cell_x, cell_y) else {
+            return;
+        };
+        let dots = grid.dots(px.inner());
+        let dot = grid.bits(x % grid.columns(), y % grid.rows());
+        self.set(cell_x, cell_y, color(grid.pixel(dots | dot)));
+    }
+
+    /// Draw a line of dots, see dot
+    ///
+    /// # Arguments
+    ///
+    /// * `from` - (x, y) position of the first dot
+    /// * `to` - (x, y) position of the last dot
+    /// * `grid` - The dots inside each cell
+    /// * `color` - The GraphPixel variant to draw the cells with
+    pub fn dot_line(
+        &mut self,
+        from: (usize, usize),
+        to: (usize, usize),
+        grid: &SubCell,
+        color: fn(Pixel) -> GraphPixel<Pixel>,
+    ) {
+        for (x, y) in line_points(from, to) {
+            self.dot(x, y, grid, color);
+        }
+    }
+
+    /// Draw a line of cells, all set to the same pixel
+    ///
+    /// # Arguments
+    ///
+    /// * `from` - (x, y) position of the first cell
+    /// * `to` - (x, y) position of the last cell
+    /// * `px` - The pixel to set the cells to
+    pub fn line(&mut self, from: (usize, usize), to: (usize, usize), px: GraphPixel<Pixel>) {
+        for (x, y) in line_points(from, to) {
+            self.set(x, y, px.clone());
+        }
+    }
+
+    /// Draw the outline of a rectangle with box drawing characters.
+    /// A rectangle of a single row is drawn as a horizontal line, one of a single column
+    /// as a vertical line, and nothing is drawn if the width or height is 0.
+    ///
+    /// # Arguments
+    ///
+    /// * `x` - Column of the left side
+    /// * `y` - Row of the top side
+    /// * `width` - Number of columns, including both sides
+    /// * `height` - Number of rows, including both sides
+    /// * `color` - The GraphPixel variant to draw the outline with
+    pub fn rect(
+        &mut self,
+        x: usize,
+        y: usize,
+        width: usize,
+        height: usize,
+        color: fn(Pixel) -> GraphPixel<Pixel>,
+    ) {
+        if width == 0 || height == 0 {
+            return;
+        }
+        if height == 1 {
+            self.line((x, y), (x + width - 1, y), color(Pixel::Char('─')));
+            return;
+        }
+        if width == 1 {
+            self.line((x, y), (x, y + height - 1), color(Pixel::Char('│')));
+            return;
+        }
+        let (right, bottom) = (x + width - 1, y + height - 1);
+        self.line((x + 1, y), (right - 1, y), color(Pixel::Char('─')));
+        self.line(
+            (x + 1, bottom),
+            (right - 1, bottom),
+            color(Pixel::Char('─')),
+        );
+        self.line((x, y + 1), (x, bottom - 1), color(Pixel::Char('│')));
+        self.line((right, y + 1), (right, bottom - 1), color(Pixel::Char('│')));
+        self.set(x, y, color(Pixel::Char('┌')));
+        self.set(right, y, color(Pixel::Char('┐')));
+        self.set(x, bottom, color(Pixel::Char('└')));
+        self.set(right, bottom, color(Pixel::Char('┘')));
+    }
+
+    /// Set every cell of a rectangle to the same pixel
+    ///
+    /// # Arguments
+    ///
+    /// * `x` - Column of the left side
+    /// * `y` - Row of the top side
+    /// * `width` - Number of columns
+    /// * `height` - Number of rows
+    /// * `px` - The pixel to set the cells to
+    pub fn fill(&mut self, x: usize, y: usize, width: usize, height: usize, px: GraphPixel<Pixel>) {
+        for row in y..y + height {
+            for column in x..x + width {
+                self.set(column, row, px.clone());
+            }
+        }
+    }
+
+    /// Write text from (x, y) towards the right, measured by display width, see text::cells.
+    /// The text stops at the first character that does not fit on the canvas.
+    ///
+    /// # Arguments
+    ///
+    /// * `x` - Column of the first character
+    /// * `y` - Row of the text
+    /// * `text` - The text to write
+    /// * `color` - The GraphPixel variant to write the text with
+    pub fn text(&mut self, x: usize, y: usize, text: &str, color: fn(Pixel) -> GraphPixel<Pixel>) {
+        if y >= self.height {
+            return;
+        }
+        for (j, cell) in cells(text).into_iter().enumerate() {
+            // A wide character needs room for both of its columns
+            if x + j + display_width(&cell).max(1) > self.width {
+                return;
+            }
+            self.set(x + j, y, color(Pixel::Text(cell)));
+        }
+    }
+
+    /// Change the size of the canvas, keeping what is drawn at the same position.
+    /// New cells are blank, and cells outside the new size are dropped.
+    ///
+    /// # Arguments
+    ///
+    /// * `width` - The new number of columns
+    /// * `height` - The new number of rows
+    pub fn resize(&mut self, width: usize, height: usize) {
+        self.place(0, 0, width, height);
+    }
+
+    /// Add blank rows and columns around what is drawn on the canvas
+    ///
+    /// # Arguments
+    ///
+    /// * `top` - Number of rows added above
+    /// * `bottom` - Number of rows added below
+    /// * `left` - Number of columns added left
+    /// * `right` - Number of columns added right
+    pub fn expand(&mut self, top: usize, bottom: usize, left: usize, right: usize) {
+        let (width, height) = (self.width + left + right, self.height + top + bottom);
+        self.place(top, left, width, height);
+    }
+
+    /// Move everything `top` rows down and `left` columns right, onto a canvas of a new size
+    fn place(&mut self, top: usize, left: usize, width: usize, height: usize) {
+        let mut cells = vec![GraphPixel::default(); width * height];
+        for (i, px) in self.cells.drain(..).enumerate() {
+            let (x, y) = (i % self.width + left, i / self.width + top);
+            if x < width && y < height {
+                cells[y * width + x] = px;
+            }
+        }
+        self.cells = cells;
+        self.width = width;
+        self.height = height;
+    }
+}
+
+impl std::fmt::Display for Canvas {
+    /// Turn canvas into a string
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        for (i, px) in self.cells.iter().enumerate() {
+            write!(f, "{}", px)?;
+            if (i + 1) % self.width == 0 && i < (self.height * self.width - 1) {
+                writeln!(f)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// The positions of a line from one point to another, using Bresenham's line algorithm
+///
+/// # Arguments
+///
+/// * `from` - (x, y) position of the first point
+/// * `to` - (x, y) position of the last point
+fn line_points(from: (usize, usize), to: (usize, usize)) -> Vec<(usize, usize)> {
+    let (mut x, mut y) = (from.0 as i64, from.1 as i64);
+    let (x1, y1) = (to.0 as i64, to.1 as i64);
+    let dx = (x1 - x).abs();
+    let dy = -(y1 - y).abs();
+    let step_x = if x < x1 { 1 } else { -1 };
+    let step_y = if y < y1 { 1 } else { -1 };
+    let mut error = dx + dy;
+    let mut points = Vec::new();
+    loop {
+        points.push((x as usize, y as usize));
+        if x == x1 && y == y1 {
+            break;
+        }
+        let doubled = 2 * error;
+        if doubled >= dy {
+            error += dy;
+            x += step_x;
+        }
+        if doubled <= dx {
+            error += dx;
+            y += step_y;
+        }
+    }
+    points
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn line_points_of_straight_lines() {
+        assert_eq!(
+            line_points((0, 1), (3, 1)),
+            vec![(0, 1), (1, 1), (2, 1), (3, 1)]
+        );
+        assert_eq!(line_points((2, 2), (2, 0)), vec![(2, 2), (2, 1), (2, 0)]);
+        assert_eq!(line_points((0, 0), (2, 2)), vec![(0, 0), (1, 1), (2, 2)]);
+    }
+
+    #[test]
+    fn line_points_are_connected() {
+        for (from, to) in [((0, 0), (7, 3)), ((7, 3), (0, 0)), ((1, 9), (4, 0))] {
+            let points = line_points(from, to);
+            assert_eq!((points[0], points[points.len() - 1]), (from, to));
+            assert!(points
+                .windows(2)
+                .all(|w| w[0].0.abs_diff(w[1].0) <= 1 && w[0].1.abs_diff(w[1].1) <= 1));
+        }
+    }
+
+    #[test]
+    fn rect_outline() {
+        let mut canvas = Canvas::new(4, 3);
+        canvas.rect(0, 0, 4, 3, GraphPixel::Normal);
+        assert_eq!(canvas.to_string(), "┌──┐\n│  │\n└──┘");
+    }
+
+    #[test]
+    fn rect_of_single_row_or_column() {
+        let mut canvas = Canvas::new(4, 3);
+        canvas.rect(0, 0, 3, 1, GraphPixel::Normal);
+        canvas.rect(3, 0, 1, 3, GraphPixel::Normal);
+        canvas.rect(0, 2, 0, 5, GraphPixel::Normal);
+        assert_eq!(canvas.to_string(), "───│\n   │\n   │");
+    }
+
+    #[test]
+    fn dots_share_a_cell() {
+        let mut canvas = Canvas::new(2, 1);
+        let grid = SubCell::Braille(BrailleType::Dot8);
+        canvas.dot(0, 0, &grid, GraphPixel::Normal);
+        canvas.dot(1, 3, &grid, GraphPixel::Normal);
+        canvas.dot_line((2, 0), (3, 3), &grid, GraphPixel::Normal);
+        assert_eq!(canvas.to_string(), "⢁⢣");
+    }
+
+    #[test]
+    fn text_stops_at_the_edge() {
+        let mut canvas = Canvas::new(5, 2);
+        canvas.text(1, 0, "abcdef", GraphPixel::Normal);
+        // The second wide character would need a column past the edge
+        canvas.text(0, 1, "日本語", GraphPixel::Normal);
+        assert_eq!(canvas.to_string(), " abcd\n日本 ");
+    }
+
+    #[test]
+    fn expand_keeps_what_is_drawn() {
+        let mut canvas = Canvas::new(2, 1);
+        canvas.text(0, 0, "ab", GraphPixel::Normal);
+        canvas.expand(1, 0, 1, 1);
+        assert_eq!(canvas.to_string(), "    \n ab ");
+        canvas.resize(2, 2);
+        assert_eq!(canvas.to_string(), "  \n a");
+    }
+}
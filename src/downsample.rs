@@ -0,0 +1,352 @@
+/// How samples should be combined when there are more samples than room for them
+#[derive(PartialEq, Clone, Default)]
+pub enum Downsample {
+    /// Keep the first sample of each column, and drop the rest
+    #[default]
+    Nearest,
+    /// The mean of the samples of each column
+    Mean,
+    /// The smallest and largest sample of each column, drawn as a vertical span
+    /// where the graph type allows it, otherwise the largest sample is drawn
+    MinMax,
+    /// Largest-Triangle-Three-Buckets, keeps the sample of each column
+    /// that best preserves the shape of the series
+    Lttb,
+}
+
+/// The range of samples that are combined into value `i` of `count`,
+/// when there are `len` samples
+fn bucket(i: usize, len: usize, count: usize) -> std::ops::Range<usize> {
+    let factor = len as f64 / count as f64;
+    let start = (i as f64 * factor) as usize;
+    let end = (((i + 1) as f64 * factor) as usize).clamp(start + 1, len);
+    start..end
+}
+
+/// Downsample by keeping the sample closest to the start of each new sample,
+/// samples shorter than `count` are returned as they are.
+///
+/// # Arguments
+///
+/// * `values` - The values to downsample
+/// * `count` - The number of values to keep
+pub fn nearest(values: &[f64], count: usize) -> Vec<f64> {
+    if values.len() < count {
+        return values.to_vec();
+    }
+    (0..count)
+        .map(|i| values[bucket(i, values.len(), count).start])
+        .collect()
+}
+
+/// Downsample by taking the mean of the samples of each new sample, ignoring NaN.
+/// Samples shorter than `count` are returned as they are.
+///
+/// # Arguments
+///
+/// * `values` - The values to downsample
+/// * `count` - The number of values to keep
+pub fn mean(values: &[f64], count: usize) -> Vec<f64> {
+    if values.len() < count {
+        return values.to_vec();
+    }
+    (0..count)
+        .map(|i| {
+            let samples = values[bucket(i, values.len(), count)]
+                .iter()
+                .filter(|y| !y.is_nan());
+            let n = samples.clone().count();
+            if n == 0 {
+                f64::NAN
+            } else {
+                samples.sum::<f64>() / n as f64
+            }
+        })
+        .collect()
+}
+
+/// Downsample by keeping both the smallest and the largest of the samples of each new sample,
+/// returned as (min, max), ignoring NaN.
+/// Samples shorter than `count` are returned as they are, as both min and max.
+///
+/// # Arguments
+///
+/// * `values` - The values to downsample
+/// * `count` - The number of values to keep
+pub fn min_max(values: &[f64], count: usize) -> (Vec<f64>, Vec<f64>) {
+    if values.len() < count {
+        return (values.to_vec(), values.to_vec());
+    }
+    (0..count)
+        .map(|i| {
+            let samples = values[bucket(i, values.len(), count)]
+                .iter()
+                .cloned()
+                .filter(|y| !y.is_nan());
+            let min = samples.clone().fold(f64::INFINITY, f64::min);
+            let max = samples.fold(f64::NEG_INFINITY, f64::max);
+            if min > max {
+                (f64::NAN, f64::NAN)
+            } else {
+                (min, max)
+            }
+        })
+        .unzip()
+}
+
+/// Downsample with the Largest-Triangle-Three-Buckets algorithm.
+/// The first and last sample are kept, and the samples in between are divided into buckets.
+/// From each bucket, the sample making the largest triangle with the sample kept from
+/// the previous bucket and the mean of the next bucket is kept.
+/// The samples are assumed to be evenly spaced, NaN samples are only kept if a bucket has
+/// nothing else. Samples shorter than `count` are returned as they are.
+///
+/// # Arguments
+///
+/// * `values` - The values to downsample
+/// * `count` - The number of values to keep
+pub fn lttb(values: &[f64], count: usize) -> Vec<f64> {
+    if values.len() <= count || count < 3 {
+        return nearest(values, count);
+    }
+    let inner = values.len() - 2;
+    let buckets = count - 2;
+    // The buckets exclude the first and last sample
+    let inner_bucket = |i: usize| {
+        let range = bucket(i, inner, buckets);
+        range.start + 1..range.end + 1
+    };
+
+    let mut kept = Vec::with_capacity(count);
+    kept.push(values[0]);
+    let mut previous = (0.0, values[0]);
+    for i in 0..buckets {
+        let next = if i + 1 < buckets {
+            inner_bucket(i + 1)
+        } else {
+            values.len() - 1..values.len()
+        };
+        let next_samples = next.clone().filter(|x| !values[*x].is_nan());
+        let n = next_samples.clone().count().max(1) as f64;
+        let next_mean = (
+            next_samples.clone().map(|x| x as f64).sum::<f64>() / n,
+            next_samples.map(|x| values[x]).sum::<f64>() / n,
+        );
+
+        let (mut best, mut best_area) = (None, -1.0);
+        for x in inner_bucket(i) {
+            let y = values[x];
+            if y.is_nan() {
+                continue;
+            }
+            let area = triangle_area(previous, (x as f64, y), next_mean);
+            if best.is_none() || area > best_area {
+                best = Some(x);
+                best_area = area;
+            }
+        }
+        match best {
+            Some(x) => {
+                kept.push(values[x]);
+                previous = (x as f64, values[x]);
+            }
+            None => kept.push(f64::NAN),
+        }
+    }
+    kept.push(values[values.len() - 1]);
+    kept
+}
+
+/// Downsample samples with unevenly spaced x values onto `count` evenly spaced columns,
+/// from the smallest to the largest x value. The samples are combined into the column closest
+/// to their x value, samples without a finite x value are left out.
+/// Returns the (smallest, largest) value of each column for Downsample::MinMax, and the combined
+/// value as both for the other methods. Columns without samples are None, columns where no
+/// sample has a value are NaN.
+///
+/// # Arguments
+///
+/// * `values` - The values to downsample
+/// * `x_values` - The x value of each sample
+/// * `count` - The number of columns
+/// * `method` - How the samples of each column are combined,
+///   Downsample::Nearest keeps the sample closest to the column
+pub fn uneven(
+    values: &[f64],
+    x_values: &[f64],
+    count: usize,
+    method: &Downsample,
+) -> Vec<Option<(f64, f64)>> {
+    let mut samples: Vec<(f64, f64)> = x_values
+        .iter()
+        .cloned()
+        .zip(values.iter().cloned())
+        .filter(|(x, _)| x.is_finite())
+        .collect();
+    samples.sort_by(|a, b| a.0.total_cmp(&b.0));
+    let (Some(first), Some(last)) = (samples.first(), samples.last()) else {
+        return vec![None; count];
+    };
+    let (min_x, max_x) = (first.0, last.0);
+    let column_x = |c: usize| {
+        if count < 2 {
+            min_x
+        } else {
+            min_x + c as f64 * (max_x - min_x) / (count - 1) as f64
+        }
+    };
+
+    // The samples of each column, as a range of the sorted samples
+    let mut buckets = vec![0..0; count];
+    for (i, (x, _)) in samples.iter().enumerate() {
+        let column = if max_x > min_x && count > 1 {
+            ((x - min_x) / (max_x - min_x) * (count - 1) as f64).round() as usize
+        } else {
+            0
+        };
+        let Some(bucket) = buckets.get_mut(column) else {
+            continue;
+        };
+        if bucket.start == bucket.end {
+            *bucket = i..i + 1;
+        } else {
+            bucket.end = i + 1;
+        }
+    }
+
+    let mut columns = Vec::with_capacity(count);
+    // The sample kept from the previous column, for Downsample::Lttb
+    let mut previous: Option<(f64, f64)> = None;
+    for (c, bucket) in buckets.iter().enumerate() {
+        let column = &samples[bucket.clone()];
+        if column.is_empty() {
+            columns.push(None);
+            continue;
+        }
+        let y_values: Vec<f64> = column.iter().map(|(_, y)| *y).collect();
+        let combined = match method {
+            Downsample::Nearest => {
+                let target = column_x(c);
+                column
+                    .iter()
+                    .min_by(|a, b| (a.0 - target).abs().total_cmp(&(b.0 - target).abs()))
+                    .map(|(_, y)| (*y, *y))
+            }
+            Downsample::Mean => mean(&y_values, 1).first().map(|y| (*y, *y)),
+            Downsample::MinMax => {
+                let (min, max) = min_max(&y_values, 1);
+                min.first().cloned().zip(max.first().cloned())
+            }
+            Downsample::Lttb => {
+                // The mean of the next column with samples, as in lttb
+                let next = buckets[c + 1..]
+                    .iter()
+                    .find(|bucket| !bucket.is_empty())
+                    .map(|bucket| {
+                        let finite: Vec<&(f64, f64)> = samples[bucket.clone()]
+                            .iter()
+                            .filter(|(_, y)| !y.is_nan())
+                            .collect();
+                        let n = finite.len().max(1) as f64;
+                        (
+                            finite.iter().map(|(x, _)| x).sum::<f64>() / n,
+                            finite.iter().map(|(_, y)| y).sum::<f64>() / n,
+                        )
+                    });
+                let mut finite = column.iter().filter(|(_, y)| !y.is_nan());
+                // The first and last sample are kept, like lttb does
+                let kept = match (previous, next) {
+                    (Some(previous), Some(next)) => finite.max_by(|a, b| {
+                        triangle_area(previous, **a, next)
+                            .total_cmp(&triangle_area(previous, **b, next))
+                    }),
+                    (None, _) => finite.next(),
+                    (_, None) => finite.next_back(),
+                };
+                if let Some(sample) = kept {
+                    previous = Some(*sample);
+                }
+                Some(kept.map_or((f64::NAN, f64::NAN), |(_, y)| (*y, *y)))
+            }
+        };
+        columns.push(combined);
+    }
+    columns
+}
+
+/// Twice the area of the triangle between three points, the factor does not matter
+/// when comparing areas
+fn triangle_area(a: (f64, f64), b: (f64, f64), c: (f64, f64)) -> f64 {
+    ((a.0 - c.0) * (b.1 - a.1) - (a.0 - b.0) * (c.1 - a.1)).abs()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::same;
+
+    #[test]
+    fn nearest_keeps_first_of_each_bucket() {
+        assert_eq!(
+            nearest(&[1.0, 2.0, 3.0, 4.0, 5.0, 6.0], 3),
+            vec![1.0, 3.0, 5.0]
+        );
+        assert_eq!(nearest(&[1.0, 2.0], 4), vec![1.0, 2.0]);
+    }
+
+    #[test]
+    fn mean_of_each_bucket() {
+        assert!(same(
+            &mean(&[1.0, 3.0, f64::NAN, 6.0, f64::NAN, f64::NAN], 3),
+            &[2.0, 6.0, f64::NAN]
+        ));
+    }
+
+    #[test]
+    fn min_max_of_each_bucket() {
+        let (min, max) = min_max(&[1.0, 5.0, 3.0, 2.0, f64::NAN, 4.0], 3);
+        assert_eq!(min, vec![1.0, 2.0, 4.0]);
+        assert_eq!(max, vec![5.0, 3.0, 4.0]);
+    }
+
+    #[test]
+    fn min_max_of_nan_bucket() {
+        let (min, max) = min_max(&[f64::NAN, f64::NAN, 1.0, 2.0], 2);
+        assert!(same(&min, &[f64::NAN, 1.0]));
+        assert!(same(&max, &[f64::NAN, 2.0]));
+    }
+
+    #[test]
+    fn lttb_keeps_peaks_and_ends() {
+        assert_eq!(
+            lttb(&[0.0, 1.0, 0.0, 5.0, 0.0, 1.0, 0.0], 3),
+            vec![0.0, 5.0, 0.0]
+        );
+    }
+
+    #[test]
+    fn lttb_skips_nan() {
+        assert!(same(
+            &lttb(&[0.0, f64::NAN, f64::NAN, 3.0, 0.0], 4),
+            &[0.0, f64::NAN, 3.0, 0.0]
+        ));
+    }
+
+    #[test]
+    fn uneven_combines_closest_samples() {
+        let x_values = [0.0, 1.0, 1.2, f64::NAN, 10.0];
+        let values = [1.0, 2.0, 3.0, 100.0, 4.0];
+        assert_eq!(
+            uneven(&values, &x_values, 3, &Downsample::Mean),
+            vec![Some((2.0, 2.0)), None, Some((4.0, 4.0))]
+        );
+        assert_eq!(
+            uneven(&values, &x_values, 3, &Downsample::MinMax),
+            vec![Some((1.0, 3.0)), None, Some((4.0, 4.0))]
+        );
+        assert_eq!(
+            uneven(&values, &x_values, 3, &Downsample::Nearest),
+            vec![Some((1.0, 1.0)), None, Some((4.0, 4.0))]
+        );
+    }
+}
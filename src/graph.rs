@@ -1,3 +1,15 @@
+pub use crate::canvas::{blc, brc, BlockType, BrailleType};
+use crate::canvas::{Canvas, GraphPixel, Pixel, SubCell};
+use crate::downsample::{self, Downsample};
+use crate::histogram::{bin_counts, bin_edges, Binning};
+use crate::stats::Stats;
+use crate::svg::{self, Svg};
+use crate::text::{cells, char_width, display_width, truncate};
+use crate::ticks::{
+    format_si, format_tick, format_time, fraction, half_span, lerp, log_ticks, nice_ticks,
+    si_width, time_ticks,
+};
+
 const ASCII_0: char = '─';
 const ASCII_1: char = '│';
 const ASCII_2: char = '╭';
@@ -5,153 +17,230 @@ const ASCII_3: char = '╰';
 const ASCII_4: char = '╮';
 const ASCII_7: char = '╯';
 
-/// Convenience function for converting a bitstring to a 6dot braille unicode character (brc: braille char)
-///
-/// # Arguments
-///
-/// - `i` - Bitstring, representing the dots of the braille character as below:
-///         1  4 | 1  4
-///         2  5 | 2  5
-///         3  6 | 3  6
-///              | 7  8
-///         If this only supported the 6dot, it could have used u8
-///         the brr function is useful for mapping a sensible way to use dot8, to the actual format
-/// - `btype` - Which braile variant the bitstring represents (dot6 or dot8)
-pub fn brc(i: u32) -> char {
-    const BRAILLE_UNICODE_OFFSET: u32 = 0x2800;
-    if i == 0 {
-        ' '
-    } else if i < 255 {
-        std::char::from_u32(BRAILLE_UNICODE_OFFSET + i as u32).unwrap()
-    } else {
-        ' '
-    }
-}
+/// Blocks filling the lower 1/8 to 8/8 of a character, used for bars growing upwards
+const BAR_UP: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
 
-/// Map sensible braille mapping to legacy (actual) mapping (brr : braille real)
-fn brr(i: u32, btype: &BrailleType) -> u32 {
-    match btype {
-        BrailleType::Dot6 => i,
-        // Keep bits 0, 1, 2 and 7, move bit 3 to bit 6, and left shift bits 4, 5 and 6
-        BrailleType::Dot8 => (i & 135) | ((i & 8) << 3) | ((i & 112) >> 1),
-    }
+/// Available options for how the graph should look
+#[derive(PartialEq, Clone, Default)]
+pub enum GraphType {
+    /// Use only * symbols
+    #[default]
+    Star,
+    /// Use pretty characters from the ascii range
+    Ascii,
+    /// Draw using braille unicode characters
+    Braille(BrailleType),
+    /// Vertical bars, using blocks with eighths of a character of resolution
+    Bar,
+    /// A single line of blocks for each series, without axis
+    Spark,
+    /// The area between zero and the values is filled, series drawn later are drawn on top
+    Area(AreaFill),
+    /// Lines drawn with half blocks or quadrant blocks
+    Blocks(BlockType),
+    /// A braille dot for each (x, y) pair, the x values are scaled like the y values
+    /// instead of being resampled to columns
+    Scatter,
 }
 
-#[derive(Clone)]
-enum Pixel {
-    Char(char),
-    Braille(u32),
+/// How the area under the values is filled in GraphType::Area
+#[derive(PartialEq, Clone, Default)]
+pub enum AreaFill {
+    /// Blocks with eighths of a character of resolution, like GraphType::Bar
+    #[default]
+    Block,
+    /// Braille dots, with two samples in each column and four dots in each row
+    Braille,
+    /// Fill whole characters with this character
+    Glyph(char),
 }
 
-impl std::fmt::Display for Pixel {
-    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
-        write!(
-            f,
-            "{}",
-            match self {
-                Pixel::Char(c) => format!("{}", c),
-                Pixel::Braille(i) => format!("{}", brc(*i)),
-            }
-        )
-    }
+/// How the values of the x-axis should be written in the axis labels
+#[derive(PartialEq, Clone, Default)]
+pub enum XFormat {
+    /// Plain numbers, such as a sample index
+    #[default]
+    Number,
+    /// Unix timestamps in seconds, written as UTC date and/or time depending on the range
+    Time,
 }
 
-#[derive(Clone)]
-#[allow(dead_code)]
-enum GraphPixel<T> {
-    Normal(T),
-    Green(T),
-    Blue(T),
-    Red(T),
-    Yellow(T),
-    Magenta(T),
-    Cyan(T),
-    Blank,
+/// How values are placed along the y-axis
+#[derive(PartialEq, Clone, Default)]
+pub enum YScale {
+    /// Values are evenly spaced
+    #[default]
+    Linear,
+    /// Powers of 10 are evenly spaced, values less than or equal to zero are not drawn
+    Log10,
+    /// Powers of 2 are evenly spaced, values less than or equal to zero are not drawn
+    Log2,
 }
 
-impl<T> std::default::Default for GraphPixel<T> {
-    fn default() -> Self {
-        GraphPixel::Blank
+impl YScale {
+    /// The base of the logarithm, if the scale is logarithmic
+    fn base(&self) -> Option<f64> {
+        match self {
+            YScale::Linear => None,
+            YScale::Log10 => Some(10.0),
+            YScale::Log2 => Some(2.0),
+        }
+    }
+
+    /// Transform a value to its position on the y-axis,
+    /// this is NaN for values that cannot be drawn
+    fn apply(&self, y: f64) -> f64 {
+        match self.base() {
+            None => y,
+            Some(_) if y <= 0.0 => f64::NAN,
+            Some(base) => y.log(base),
+        }
     }
-}
 
-impl<T: std::fmt::Display> std::fmt::Display for GraphPixel<T> {
-    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
-        write!(
-            f,
-            "{}",
-            match self {
-                GraphPixel::Normal(c) => format!("{}", c),
-
-                #[cfg(feature = "ansi")]
-                GraphPixel::Green(c) => format!("\x1b[32m{}\x1b[0m", c),
-                #[cfg(feature = "ansi")]
-                GraphPixel::Blue(c) => format!("\x1b[34m{}\x1b[0m", c),
-                #[cfg(feature = "ansi")]
-                GraphPixel::Red(c) => format!("\x1b[31m{}\x1b[0m", c),
-                #[cfg(feature = "ansi")]
-                GraphPixel::Yellow(c) => format!("\x1b[33m{}\x1b[0m", c),
-                #[cfg(feature = "ansi")]
-                GraphPixel::Magenta(c) => format!("\x1b[33m{}\x1b[0m", c),
-                #[cfg(feature = "ansi")]
-                GraphPixel::Cyan(c) => format!("\x1b[36m{}\x1b[0m", c),
-
-                #[cfg(not(feature = "ansi"))]
-                GraphPixel::Green(c) => format!("{}, c),
-                #[cfg(not(feature = "ansi"))]
-                GraphPixel::Blue(c) => format!("{}, c),
-                #[cfg(not(feature = "ansi"))]
-                GraphPixel::Red(c) => format!("{}, c),
-                #[cfg(not(feature = "ansi"))]
-                GraphPixel::Yellow(c) => format!("{}, c),
-                #[cfg(not(feature = "ansi"))]
-                GraphPixel::Magenta(c) => format!("{}, c),
-                #[cfg(not(feature = "ansi"))]
-                GraphPixel::Cyan(c) => format!("{}, c),
-
-                GraphPixel::Blank => String::from(" "),
-            }
-        )
+    /// Transform a position on the y-axis back to the value
+    fn invert(&self, y: f64) -> f64 {
+        match self.base() {
+            None => y,
+            Some(base) => base.powf(y),
+        }
     }
 }
 
-#[derive(PartialEq, Clone)]
-pub enum BrailleType {
-    Dot6,
-    Dot8,
+/// The y-axis a series is drawn against, each axis has its own range and ticks
+#[derive(PartialEq, Clone, Default)]
+pub enum YAxis {
+    /// The axis left of the graph
+    #[default]
+    Left,
+    /// The axis right of the graph, it is only drawn if a series uses it
+    Right,
 }
 
-/// Available options for how the graph should look
-#[derive(PartialEq, Clone)]
-pub enum GraphType {
-    /// Use only * symbols
-    Star,
-    /// Use pretty characters from the ascii range
-    Ascii,
-    /// Draw using braille unicode characters
-    Braille(BrailleType),
+/// Colors used for the series of a graph, the first series gets the first color and so on.
+/// If there are more series than colors, the colors will be reused.
+pub(crate) const SERIES_COLORS: [fn(Pixel) -> GraphPixel<Pixel>; 6] = [
+    GraphPixel::Green,
+    GraphPixel::Blue,
+    GraphPixel::Magenta,
+    GraphPixel::Cyan,
+    GraphPixel::Yellow,
+    GraphPixel::Red,
+];
+
+/// Colors that can be picked for elements of a graph, such as thresholds
+#[derive(PartialEq, Clone, Default)]
+pub enum Color {
+    #[default]
+    Red,
+    Yellow,
+    Green,
+    Blue,
+    Magenta,
+    Cyan,
 }
 
-impl std::default::Default for GraphType {
-    fn default() -> Self {
-        GraphType::Star
+impl Color {
+    /// The GraphPixel variant of the color
+    pub(crate) fn pixel(&self) -> fn(Pixel) -> GraphPixel<Pixel> {
+        match self {
+            Color::Red => GraphPixel::Red,
+            Color::Yellow => GraphPixel::Yellow,
+            Color::Green => GraphPixel::Green,
+            Color::Blue => GraphPixel::Blue,
+            Color::Magenta => GraphPixel::Magenta,
+            Color::Cyan => GraphPixel::Cyan,
+        }
     }
 }
 
+/// Character used for the horizontal lines of thresholds
+const THRESHOLD_LINE: char = '┄';
+
+/// Characters used for each series in GraphType::Star, so the series can be told apart without color
+const SERIES_STARS: [char; 4] = ['*', '+', 'o', 'x'];
+
 impl GraphType {
-    fn btype(&self) -> BrailleType {
+    /// The grid of dots inside each character, if the graph type draws dots
+    fn sub_cell(&self) -> Option<SubCell> {
+        match self {
+            GraphType::Braille(btype) => Some(SubCell::Braille(btype.clone())),
+            GraphType::Scatter | GraphType::Area(AreaFill::Braille) => {
+                Some(SubCell::Braille(BrailleType::Dot8))
+            }
+            GraphType::Blocks(btype) => Some(SubCell::Block(btype.clone())),
+            _ => None,
+        }
+    }
+}
+
+/// Reasons a graph cannot be built, see GraphBuilder::try_build
+#[derive(PartialEq, Clone, Debug)]
+pub enum GraphError {
+    /// The width or the height of the canvas is zero
+    EmptyCanvas,
+    /// The fixed lower bound of the y-axis is above the fixed upper bound
+    InvalidRange { y_min: f64, y_max: f64 },
+}
+
+impl std::fmt::Display for GraphError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
         match self {
-            GraphType::Braille(b) => b.clone(),
-            _ => panic!("cannot get btype on non Braille GraphType"),
+            GraphError::EmptyCanvas => write!(f, "the canvas has no width or no height"),
+            GraphError::InvalidRange { y_min, y_max } => {
+                write!(
+                    f,
+                    "the y-axis minimum {} is above the maximum {}",
+                    y_min, y_max
+                )
+            }
         }
     }
 }
 
+impl std::error::Error for GraphError {}
+
+/// Rows and columns kept around the graph by reserve_frame, for the title, labels and legend
+#[derive(Default)]
+struct Frame {
+    /// Rows above the graph, for the title
+    top: usize,
+    /// Rows under the graph, for the x-axis label
+    bottom: usize,
+    /// Columns left of the graph, for the y-axis label
+    left: usize,
+    /// Columns right of the graph, for the label of the right y-axis
+    right_label: usize,
+    /// Columns right of the graph, for the legend
+    legend: usize,
+    /// Rows under the graph and the x-axis label, for the statistics of each series
+    footer: usize,
+}
+
+/// The samples of each series as they were drawn by try_build, kept so they can be drawn
+/// again as shapes in a SVG
+#[derive(Clone, Default)]
+struct Plot {
+    /// The level of each sample, scaled like the samples that are drawn but not rounded
+    levels: Vec<Vec<f64>>,
+    /// The level of the smallest value of each column, see y_lows
+    lows: Vec<Vec<f64>>,
+    /// The dot column of each sample of a scatter plot
+    dots: Vec<usize>,
+    /// The level the bars and areas of each series start from, see bar_baseline
+    baselines: Vec<usize>,
+    /// The last series that drew on each cell of the draw area, row by row,
+    /// None for the cells overwritten by the markers of draw_overflow
+    cells: Vec<Option<usize>>,
+    /// The cells of the draw area given the color of each threshold by draw_thresholds
+    recolored: Vec<(Color, Vec<(usize, usize)>)>,
+}
+
 /// Temporary variables used while building a graph
 #[allow(dead_code)]
+#[derive(Clone)]
 pub struct GraphBuilder {
-    /// A array of pixels, this will ultimately be turned to a string, is initialized to width * height
-    elements: Vec<GraphPixel<Pixel>>,
+    /// The canvas the graph is drawn on, this will ultimately be turned to a string
+    canvas: Canvas,
     /// Width of canvas
     width: usize,
     /// Height of canvas
@@ -166,8 +255,12 @@ pub struct GraphBuilder {
     row_offset: usize,
     /// The values of the x-axis of the graph
     x_values: Vec<f64>,
-    /// The values of the y-axis of the graph
+    /// The values of the y-axis of the graph, one vector for each series
     y_values: Vec<Vec<f64>>,
+    /// The name of each series, in the same order as y_values
+    series_names: Vec<String>,
+    /// The y-axis each series is drawn against, in the same order as y_values
+    series_axes: Vec<YAxis>,
     /// Decides whether axis will be drawn on the resulting graph
     enable_axis: bool,
     /// Which GraphType to use when the graph is drawn
@@ -176,6 +269,75 @@ pub struct GraphBuilder {
     cut_overflow: bool,
     /// Whether or not to use color pixels
     enable_color: bool,
+    /// Samples further apart than this on the x-axis are not joined when interpolating
+    max_gap: Option<f64>,
+    /// How the values of the x-axis labels are formatted
+    x_format: XFormat,
+    /// The row of the canvas where the x-axis labels are written, if there is room for them
+    x_label_row: Option<usize>,
+    /// Number of decimals in the axis labels
+    precision: usize,
+    /// Fixed lower bound of the y-axis
+    y_min: Option<f64>,
+    /// Fixed upper bound of the y-axis
+    y_max: Option<f64>,
+    /// Whether zero should always be inside the range of the y-axis
+    include_zero: bool,
+    /// Range of the y-axis the last time the graph was drawn, and how fast it shrinks
+    sticky: Option<((f64, f64), f64)>,
+    /// The range of the y-axis used when the graph was built
+    y_range: (f64, f64),
+    /// The range of the right y-axis used when the graph was built
+    right_y_range: (f64, f64),
+    /// How values are placed along the y-axis
+    y_scale: YScale,
+    /// How to bin the values, if the graph is a histogram of the values
+    binning: Option<Binning>,
+    /// The edges of the bins of the histogram, once the values have been binned
+    bin_edges: Option<Vec<f64>>,
+    /// Whether the min, max and last value are written after a sparkline
+    spark_values: bool,
+    /// How samples are combined when there are more samples than columns
+    downsample: Downsample,
+    /// Values where a horizontal line is drawn, anything drawn above it gets its color
+    thresholds: Vec<(f64, Color)>,
+    /// Whether the min, max and last value of each series are written next to them
+    annotate: bool,
+    /// Whether the last value of each series is written right of the graph
+    edge_value: bool,
+    /// Whether a line of statistics of each series is written under the graph
+    footer: bool,
+    /// Statistics of each series as drawn by the last call to build, if footer is set,
+    /// computed before the samples are binned or downsampled
+    stats: Vec<Stats>,
+    /// Text written centered above the graph
+    title: Option<String>,
+    /// Text written centered under the x-axis
+    x_label: Option<String>,
+    /// Text written from top to bottom, left of the y-axis
+    y_label: Option<String>,
+    /// Text written from top to bottom, right of the right y-axis
+    right_y_label: Option<String>,
+    /// Whether a box with the name of each series is drawn right of the graph
+    legend: bool,
+    /// Whether horizontal gridlines are drawn at the y-axis ticks
+    horizontal_grid: bool,
+    /// Whether vertical gridlines are drawn at the x-axis ticks
+    vertical_grid: bool,
+    /// The rows of the canvas with a y-axis tick, once the axis has been drawn
+    y_tick_rows: Vec<usize>,
+    /// The columns of the canvas with a x-axis tick, once the x-axis labels have been written
+    x_tick_columns: Vec<usize>,
+    /// The rows of the canvas with a tick of the right y-axis, once the axis has been drawn
+    right_tick_rows: Vec<usize>,
+    /// The smallest value of each column of each series, when downsampled with
+    /// Downsample::MinMax, the largest value is in y_values
+    y_lows: Vec<Vec<f64>>,
+    /// Columns where a sample of a series was outside the range of the y-axis,
+    /// as (series, column, above)
+    overflow: Vec<(usize, usize, bool)>,
+    /// The samples as they were drawn, see Plot
+    plot: Plot,
 }
 
 impl GraphBuilder {
@@ -187,7 +349,7 @@ impl GraphBuilder {
     /// * `height` - Height of the output canvas
     pub fn new(x_values: &[f64], y_values: &[f64], width: usize, height: usize) -> Self {
         GraphBuilder {
-            elements: vec![GraphPixel::default(); width * height],
+            canvas: Canvas::new(width, height),
             width,
             height,
             draw_width: width,
@@ -196,11 +358,146 @@ impl GraphBuilder {
             row_offset: 0,
             x_values: x_values.to_vec(),
             y_values: vec![y_values.to_vec()],
+            series_names: vec![String::new()],
+            series_axes: vec![YAxis::Left],
             enable_axis: false,
             graph_type: GraphType::default(),
             cut_overflow: false,
             enable_color: true,
+            max_gap: None,
+            x_format: XFormat::default(),
+            x_label_row: None,
+            precision: 1,
+            y_min: None,
+            y_max: None,
+            include_zero: false,
+            sticky: None,
+            y_range: (f64::NAN, f64::NAN),
+            right_y_range: (f64::NAN, f64::NAN),
+            overflow: Vec::new(),
+            plot: Plot::default(),
+            y_scale: YScale::default(),
+            binning: None,
+            bin_edges: None,
+            spark_values: false,
+            downsample: Downsample::default(),
+            thresholds: Vec::new(),
+            annotate: false,
+            edge_value: false,
+            footer: false,
+            stats: Vec::new(),
+            title: None,
+            x_label: None,
+            y_label: None,
+            right_y_label: None,
+            legend: false,
+            horizontal_grid: false,
+            vertical_grid: false,
+            y_tick_rows: Vec::new(),
+            x_tick_columns: Vec::new(),
+            right_tick_rows: Vec::new(),
+            y_lows: Vec::new(),
+        }
+    }
+
+    /// Create a histogram of `values`, with desired width and height.
+    /// The values are binned when the graph is built, the bins are drawn as bars
+    /// and the edges of the bins are written on the x-axis.
+    /// Series added with add_series are counted in the same bins.
+    ///
+    /// # Arguments
+    ///
+    /// * `values` - The values to count in the histogram
+    /// * `binning` - How to divide the values into bins
+    /// * `width` - Width of the output canvas
+    /// * `height` - Height of the output canvas
+    pub fn histogram(values: &[f64], binning: Binning, width: usize, height: usize) -> Self {
+        let x_values: Vec<f64> = (0..values.len()).map(|i| i as f64).collect();
+        let mut graph = GraphBuilder::new(&x_values, values, width, height);
+        graph.graph_type = GraphType::Bar;
+        graph.include_zero = true;
+        graph.binning = Some(binning);
+        graph
+    }
+
+    /// Add another series to the graph, it is drawn in its own color on the same y-axis
+    /// as the other series.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - Name of the series
+    /// * `y_values` - The values of the series, one for each of the x values of the graph
+    pub fn add_series(&mut self, name: &str, y_values: &[f64]) -> &Self {
+        self.y_values.push(y_values.to_vec());
+        self.series_names.push(name.to_string());
+        self.series_axes.push(YAxis::Left);
+        self
+    }
+
+    /// Set the name of the series given to new, it is shown in the legend and the footer
+    pub fn name(&mut self, name: &str) -> &Self {
+        self.series_names[0] = name.to_string();
+        self
+    }
+
+    /// Write a title centered above the graph, it is shortened if it is wider than the canvas
+    pub fn title(&mut self, title: &str) -> &Self {
+        self.title = Some(title.to_string());
+        self
+    }
+
+    /// Write a label centered under the x-axis
+    pub fn x_label(&mut self, label: &str) -> &Self {
+        self.x_label = Some(label.to_string());
+        self
+    }
+
+    /// Write a label from top to bottom, left of the y-axis
+    pub fn y_label(&mut self, label: &str) -> &Self {
+        self.y_label = Some(label.to_string());
+        self
+    }
+
+    /// Write a label from top to bottom, right of the right y-axis
+    pub fn right_y_label(&mut self, label: &str) -> &Self {
+        self.right_y_label = Some(label.to_string());
+        self
+    }
+
+    /// Draw series `g` against the left or the right y-axis, the series are numbered from 0
+    /// in the order they were added. The right y-axis has its own range, which is not
+    /// affected by y_bounds, sticky_range or the thresholds.
+    ///
+    /// # Arguments
+    ///
+    /// * `g` - The series to move, nothing is changed if there is no such series
+    /// * `axis` - The y-axis to draw the series against
+    pub fn series_axis(&mut self, g: usize, axis: YAxis) -> &Self {
+        if let Some(series_axis) = self.series_axes.get_mut(g) {
+            *series_axis = axis;
         }
+        self
+    }
+
+    /// Draw a box right of the graph, with the name of each series next to how it is drawn.
+    /// Series without a name are numbered from 1.
+    pub fn legend(&mut self, legend: bool) -> &Self {
+        self.legend = legend;
+        self
+    }
+
+    /// Draw faint gridlines across the graph at the ticks of the axis, and tick marks on
+    /// the border of the axis where they meet it. Anything drawn on the graph covers the gridlines.
+    /// This needs the axis, and vertical gridlines need the x-axis labels.
+    ///
+    /// # Arguments
+    ///
+    /// * `horizontal` - Whether to draw lines at the y-axis ticks
+    /// * `vertical` - Whether to draw lines at the x-axis ticks
+    pub fn grid(&mut self, horizontal: bool, vertical: bool) -> &Self {
+        self.horizontal_grid = horizontal;
+        self.vertical_grid = vertical;
+        self
     }
 
     /// Enable or disable axis in output
@@ -221,6 +518,142 @@ impl GraphBuilder {
         self
     }
 
+    /// Set the largest distance between two x values that will be interpolated across.
+    /// This is only used when the x values are unevenly spaced, columns that fall
+    /// in a larger gap between two samples are left empty.
+    ///
+    /// # Arguments
+    ///
+    /// * `max_gap` - Largest distance to interpolate across, None to always interpolate
+    pub fn max_gap(&mut self, max_gap: Option<f64>) -> &Self {
+        self.max_gap = max_gap;
+        self
+    }
+
+    /// Set how the x values are written in the labels of the x-axis
+    pub fn x_format(&mut self, x_format: XFormat) -> &Self {
+        self.x_format = x_format;
+        self
+    }
+
+    /// Set the number of decimals used in the axis labels.
+    /// Labels use SI suffixes, so this is the number of decimals before the suffix,
+    /// more decimals are used if the ticks would be impossible to tell apart otherwise.
+    pub fn precision(&mut self, precision: usize) -> &Self {
+        self.precision = precision;
+        self
+    }
+
+    /// Set a fixed range for the y-axis, instead of fitting it to the values.
+    /// Values outside the range are drawn at the edge of the graph, with a overflow marker.
+    ///
+    /// # Arguments
+    ///
+    /// * `y_min` - Lower bound of the y-axis, or None to use the smallest value
+    /// * `y_max` - Upper bound of the y-axis, or None to use the largest value
+    pub fn y_bounds(&mut self, y_min: Option<f64>, y_max: Option<f64>) -> &Self {
+        self.y_min = y_min;
+        self.y_max = y_max;
+        self
+    }
+
+    /// Always include zero in the range of the y-axis, so the graph has a baseline
+    pub fn include_zero(&mut self, include_zero: bool) -> &Self {
+        self.include_zero = include_zero;
+        self
+    }
+
+    /// Make the range of the y-axis sticky, used to keep the axis from jumping between redraws.
+    /// The range grows immediately to fit new values, but only shrinks towards the range
+    /// of the values by a fraction of the difference each time the graph is built.
+    /// Fixed bounds set with y_bounds are still respected.
+    ///
+    /// # Arguments
+    ///
+    /// * `previous` - The range of the previous graph, see y_range
+    /// * `decay` - How much of the difference to shrink by, between 0 (never) and 1 (immediately)
+    pub fn sticky_range(&mut self, previous: (f64, f64), decay: f64) -> &Self {
+        self.sticky = Some((previous, decay));
+        self
+    }
+
+    /// Set how values are placed along the y-axis, linear or logarithmic
+    pub fn y_scale(&mut self, y_scale: YScale) -> &Self {
+        self.y_scale = y_scale;
+        self
+    }
+
+    /// Write the min, max and last value of each series after the sparkline,
+    /// only used with GraphType::Spark
+    pub fn spark_values(&mut self, spark_values: bool) -> &Self {
+        self.spark_values = spark_values;
+        self
+    }
+
+    /// Set how samples are combined when there are more samples than columns.
+    /// When the x values are unevenly spaced, the samples are combined in the column closest
+    /// to them, and the columns without samples are interpolated. Downsample::Nearest then
+    /// interpolates every column instead.
+    pub fn downsample(&mut self, downsample: Downsample) -> &Self {
+        self.downsample = downsample;
+        self
+    }
+
+    /// Add a threshold, drawn as a horizontal line across the graph.
+    /// Everything drawn above the line gets the color of the threshold,
+    /// if a sample is above several thresholds, the color of the highest one is used.
+    /// The y-axis always includes the thresholds, unless it has fixed bounds.
+    ///
+    /// # Arguments
+    ///
+    /// * `value` - The value of the threshold
+    /// * `color` - The color of the line, and of samples above it
+    pub fn threshold(&mut self, value: f64, color: Color) -> &Self {
+        self.thresholds.push((value, color));
+        self
+    }
+
+    /// Write the min, max and last value of each series next to where they are drawn.
+    /// The labels are only written where they do not cover anything else,
+    /// so some of them may be left out.
+    pub fn annotate(&mut self, annotate: bool) -> &Self {
+        self.annotate = annotate;
+        self
+    }
+
+    /// Write the last value of each series right of the graph, at the row of the value.
+    /// This needs the axis, and leaves less room for the graph.
+    pub fn edge_value(&mut self, edge_value: bool) -> &Self {
+        self.edge_value = edge_value;
+        self
+    }
+
+    /// Write a line under the graph for each series, with the statistics from stats.
+    /// The lines are taken from the height of the graph, except for a spark graph,
+    /// where they are added after the sparklines.
+    pub fn footer(&mut self, footer: bool) -> &Self {
+        self.footer = footer;
+        self
+    }
+
+    /// The statistics of the samples of each series drawn by the last call to build,
+    /// in the same order as the series were added. They are only computed if footer is set,
+    /// and only cover the samples left by cut_overflow.
+    pub fn stats(&self) -> &[Stats] {
+        &self.stats
+    }
+
+    /// The range of the right y-axis used by the last call to build,
+    /// see series_axis
+    pub fn right_y_range(&self) -> (f64, f64) {
+        self.right_y_range
+    }
+
+    /// The range of the y-axis used by the last call to build
+    pub fn y_range(&self) -> (f64, f64) {
+        self.y_range
+    }
+
     /// Delete all saved samples before the last n
     /// Assumes that y_values and x_values has the same length
     ///
@@ -228,12 +661,14 @@ impl GraphBuilder {
     ///
     /// * `n` - Number of samples to keep
     pub fn keep_tail(&mut self, n: usize) -> &Self {
-        for i in 0..self.y_values.len() {
-            if self.y_values[i].len() > n {
-                self.y_values[i] = self.y_values[i][self.y_values[0].len() - n..].to_vec();
-                self.x_values = self.x_values[self.x_values.len() - n..].to_vec();
+        for values in self.y_values.iter_mut() {
+            if values.len() > n {
+                *values = values[values.len() - n..].to_vec();
             }
         }
+        if self.x_values.len() > n {
+            self.x_values = self.x_values[self.x_values.len() - n..].to_vec();
+        }
         self
     }
 
@@ -249,39 +684,114 @@ impl GraphBuilder {
         self
     }
 
-    /// Build the actual graph,
-    /// this is potentially a heavy operation, and it will mutate &self!
-    /// If you want to only see the "current state", you should clone first!
+    /// Build the actual graph, this is potentially a heavy operation.
+    /// The graph is drawn from a copy of the samples, so building again gives the same graph,
+    /// only the ranges returned by y_range and right_y_range and the stats are updated.
+    /// If the graph cannot be built, an empty string is returned, see try_build for why.
     pub fn build(&mut self) -> String {
-        if self.cut_overflow {
-            if self.graph_type == GraphType::Braille(BrailleType::Dot6) {
-                self.keep_tail(self.draw_width * 2);
-            } else if self.graph_type == GraphType::Braille(BrailleType::Dot8) {
-                self.keep_tail(self.draw_width * 2);
-            } else {
-                self.keep_tail(self.draw_width);
+        self.try_build().unwrap_or_default()
+    }
+
+    /// Build the actual graph, like build, but return why the graph could not be built.
+    ///
+    /// Data without any finite values is drawn as an empty graph, and values that are not
+    /// finite are left out, like gaps. If every value is the same, or a fixed range of the
+    /// y-axis is a single value, the values are drawn in the middle of the graph.
+    pub fn try_build(&mut self) -> Result<String, GraphError> {
+        Ok(self.render()?.to_string())
+    }
+
+    /// Draw the graph on a copy of the builder, which is returned,
+    /// and keep the ranges of the y-axes and the statistics it was drawn with, see build
+    fn render(&mut self) -> Result<GraphBuilder, GraphError> {
+        let mut graph = self.clone();
+        graph.draw_graph()?;
+        self.y_range = graph.y_range;
+        self.right_y_range = graph.right_y_range;
+        self.stats = graph.stats.clone();
+        Ok(graph)
+    }
+
+    /// Draw the graph on the canvas, this turns the samples into the levels they are drawn at
+    fn draw_graph(&mut self) -> Result<(), GraphError> {
+        if self.width == 0 || (self.height == 0 && self.graph_type != GraphType::Spark) {
+            return Err(GraphError::EmptyCanvas);
+        }
+        if let (Some(y_min), Some(y_max)) = (self.y_min, self.y_max) {
+            if y_min > y_max {
+                return Err(GraphError::InvalidRange { y_min, y_max });
             }
         }
 
-        //let min_x = self.x_values.iter().cloned().fold(f64::INFINITY, f64::min);
-        //let max_x = self
-        //    .x_values
-        //    .iter()
-        //    .cloned()
-        //    .fold(f64::NEG_INFINITY, f64::max);
-        let min_y = self.y_values[0]
+        self.draw_height = self.draw_height.min(self.height);
+        let frame = self.reserve_frame();
+        let footer_rows = frame.footer;
+        self.fit_layout();
+        if self.graph_type == GraphType::Spark {
+            self.spark_layout();
+        }
+        if self.cut_overflow && self.binning.is_none() {
+            self.keep_tail(self.draw_width * self.samples_per_column());
+        }
+        // The samples as they were read, for the statistics once the samples to draw are known
+        let read_values = if self.footer {
+            self.y_values.clone()
+        } else {
+            Vec::new()
+        };
+        if self.binning.is_some() {
+            self.bin();
+        }
+        let spark_values = if self.graph_type == GraphType::Spark && self.spark_values {
+            self.spark_values_text()
+        } else {
+            Vec::new()
+        };
+
+        // From here on, values are positions on the y-axis,
+        // positions that are not finite are not drawn
+        for y in self.y_values.iter_mut().flatten() {
+            *y = self.y_scale.apply(*y);
+            if !y.is_finite() {
+                *y = f64::NAN;
+            }
+        }
+        let (min_y, max_y) = self.find_y_range(&YAxis::Left);
+        self.y_range = (self.y_scale.invert(min_y), self.y_scale.invert(max_y));
+        let right_range = self.find_y_range(&YAxis::Right);
+        self.right_y_range = (
+            self.y_scale.invert(right_range.0),
+            self.y_scale.invert(right_range.1),
+        );
+        // The range of the y-axis each series is drawn against
+        let ranges: Vec<(f64, f64)> = self
+            .series_axes
             .iter()
-            .cloned()
-            .fold(f64::INFINITY, f64::min);
-        let max_y = self.y_values[0]
+            // fit_layout may have left out series
+            .take(self.y_values.len())
+            .map(|axis| match axis {
+                YAxis::Left => (min_y, max_y),
+                YAxis::Right => right_range,
+            })
+            .collect();
+        // Found before the samples are downsampled, so they are not lost
+        let annotations = if self.annotate && self.graph_type != GraphType::Spark {
+            self.find_annotations()
+        } else {
+            Vec::new()
+        };
+        let edge_values: Vec<Option<f64>> = self
+            .y_values
             .iter()
-            .cloned()
-            .fold(f64::NEG_INFINITY, f64::max);
+            .map(|values| values.iter().rev().find(|y| !y.is_nan()).cloned())
+            .collect();
 
-        if self.enable_axis {
+        if self.enable_axis && self.graph_type != GraphType::Spark {
+            let right_range = self.has_right_axis().then_some(right_range);
             self.draw_axis(
                 min_y,
                 max_y,
+                right_range,
                 GraphPixel::Normal(Pixel::Char(ASCII_1)),
                 GraphPixel::Normal(Pixel::Char(ASCII_0)),
                 GraphPixel::Normal(Pixel::Char('└')),
@@ -292,135 +802,1043 @@ impl GraphBuilder {
         }
 
         // Run a second time after axis has been calculated properly
-        if self.cut_overflow {
-            if self.graph_type == GraphType::Braille(BrailleType::Dot6) {
-                self.keep_tail(self.draw_width * 2);
-            } else if self.graph_type == GraphType::Braille(BrailleType::Dot8) {
-                self.keep_tail(self.draw_width * 2);
-            } else {
-                self.keep_tail(self.draw_width);
-            }
+        if self.cut_overflow && self.binning.is_none() {
+            self.keep_tail(self.draw_width * self.samples_per_column());
+        }
+        if self.footer {
+            // A histogram draws every sample, otherwise only the samples left by keep_tail
+            self.stats = read_values
+                .iter()
+                .zip(self.y_values.iter())
+                .map(|(read, values)| match self.binning {
+                    Some(_) => Stats::new(read),
+                    None => Stats::new(&read[read.len() - values.len().min(read.len())..]),
+                })
+                .collect();
         }
 
-        if true {
-            // && x_values.windows(2).all(|w| w[1] - w[0] == w[0] - w[1]) {
-            self.downsample();
+        // The dot column of each point of a scatter plot, in the same order as the samples
+        let mut scatter_dots = Vec::new();
+        if self.graph_type == GraphType::Scatter {
+            let column_count = self.draw_width * self.samples_per_column();
+            scatter_dots = scatter_columns(&self.x_values, column_count);
+            // Points without a x value cannot be placed
+            for values in self.y_values.iter_mut() {
+                for (y, x) in values.iter_mut().zip(self.x_values.iter()) {
+                    if !x.is_finite() {
+                        *y = f64::NAN;
+                    }
+                }
+            }
+            // Label the columns, so the first and last column are the smallest and largest x value
+            self.x_values = column_grid(&self.x_values, self.draw_width)
+                .into_iter()
+                .flat_map(|x| std::iter::repeat_n(x, self.samples_per_column()))
+                .collect();
+        } else if self.bin_edges.is_some() {
+            self.spread_bins();
+        } else if evenly_spaced(&self.x_values) {
+            self.downsample_columns();
         } else {
-            // If the sample size is not consistent, we should interpolate
-            todo!("interpolation is not implemented");
-            //interpolate(&y_values, &x_values, graph.width())
+            // If the sample size is not consistent, we should interpolate,
+            // onto one column per sample if there are fewer samples than columns,
+            // like evenly spaced samples
+            let column_count =
+                (self.draw_width * self.samples_per_column()).min(self.x_values.len());
+            self.y_lows.clear();
+            for g in 0..self.y_values.len() {
+                let (values, lows) = resample(
+                    &self.y_values[g],
+                    &self.x_values,
+                    column_count,
+                    self.max_gap,
+                    &self.downsample,
+                );
+                self.y_values[g] = values;
+                if self.downsample == Downsample::MinMax {
+                    self.y_lows.push(lows);
+                }
+            }
+            self.x_values = column_grid(&self.x_values, column_count);
         };
 
-        // Scale the data
-        let mut scale_height = self.draw_height;
-        if self.graph_type == GraphType::Braille(BrailleType::Dot6) {
-            scale_height = self.draw_height * 3;
-        } else if self.graph_type == GraphType::Braille(BrailleType::Dot8) {
-            scale_height = self.draw_height * 4;
-        }
-        let scale_factor = (scale_height - 1) as f64 / (max_y - min_y);
-        for i in 0..self.y_values[0].len() {
-            self.y_values[0][i] = ((self.y_values[0][i] - min_y) * scale_factor).round();
+        if self.x_label_row.is_some() {
+            self.draw_x_ticks();
         }
 
-        match self.graph_type {
-            GraphType::Star => self.draw_star(0),
-            GraphType::Ascii => self.draw_ascii(0),
-            GraphType::Braille(BrailleType::Dot6) => self.draw_braille(0),
-            GraphType::Braille(BrailleType::Dot8) => self.draw_braille(0),
+        // Scale the data
+        let scale_height = self.scale_levels();
+        let samples_per_column = self.samples_per_column();
+        let series_count = self.y_values.len();
+        // The lows of Downsample::MinMax are scaled the same way, after the values
+        let shifts: Vec<f64> = ranges.iter().map(|range| self.bar_shift(*range)).collect();
+        let all_values = self.y_values.iter_mut().chain(self.y_lows.iter_mut());
+        let mut levels = Vec::new();
+        for (g, values) in all_values.enumerate() {
+            let g = g % series_count;
+            let mut series_levels = Vec::with_capacity(values.len());
+            for (i, y) in values.iter_mut().enumerate() {
+                let level = scale_level(*y, ranges[g], scale_height);
+                // Values outside a fixed range are clipped to the edge of the graph
+                if level.round() < 0.0 || level.round() > (scale_height - 1) as f64 {
+                    let column = scatter_dots.get(i).cloned().unwrap_or(i) / samples_per_column;
+                    self.overflow.push((g, column, level > 0.0));
+                }
+                let level = (level + shifts[g]).clamp(0.0, (scale_height - 1) as f64);
+                series_levels.push(level);
+                *y = level.round();
+            }
+            levels.push(series_levels);
         }
+        let lows = levels.split_off(series_count);
 
-        self.to_string()
-    }
-
-    // Downsample using a common downsampling, this allows us to avoid doing anything
-    // with the x values.
-    // Make sure to only use one downsampling-algorithm
-    fn downsample(&mut self) {
-        for g in 0..self.y_values.len() {
-            let mut scale_width = self.draw_width;
-            if self.graph_type == GraphType::Braille(BrailleType::Dot6)
-                || self.graph_type == GraphType::Braille(BrailleType::Dot8)
-            {
-                scale_width *= 2;
+        let mut owners = vec![None; self.draw_width * self.draw_height];
+        for (g, range) in ranges.iter().enumerate() {
+            let before = self.draw_area_cells();
+            let bar_baseline = self.bar_baseline(*range);
+            match self.graph_type {
+                GraphType::Star => self.draw_star(g),
+                GraphType::Ascii => self.draw_ascii(g),
+                GraphType::Braille(_) | GraphType::Blocks(_) => self.draw_dots(g),
+                GraphType::Bar => self.draw_bar(g, bar_baseline),
+                GraphType::Area(_) => self.draw_area(g, bar_baseline),
+                GraphType::Spark => self.draw_spark(g),
+                GraphType::Scatter => self.draw_scatter(g, &scatter_dots),
             }
-            if self.y_values[g].len() < scale_width {
-                continue;
+            if !self.y_lows.is_empty() {
+                self.draw_spans(g);
             }
-
-            let factor = self.y_values[g].len() as f64 / scale_width as f64;
-            let mut new_values = Vec::with_capacity(scale_width);
-            for i in 0..scale_width {
-                let new_value = self.y_values[g][(i as f64 * factor) as usize];
-                new_values.push(new_value);
+            for (owner, (before, after)) in owners
+                .iter_mut()
+                .zip(before.iter().zip(self.draw_area_cells()))
+            {
+                if *before != after {
+                    *owner = Some(g);
+                }
             }
-            self.y_values[g] = new_values;
         }
-    }
-
-    /// Turn canvas into a string
-    pub fn to_string(&self) -> String {
-        let mut out = String::with_capacity(self.height * (self.width + 1));
-        for (i, px) in self.elements.iter().enumerate() {
-            out.push_str(&px.to_string());
-            if (i + 1) % self.width == 0 && i < (self.height * self.width - 1) {
-                out.push('\n');
+        self.plot = Plot {
+            levels,
+            lows,
+            dots: scatter_dots,
+            baselines: ranges
+                .iter()
+                .map(|range| self.bar_baseline(*range))
+                .collect(),
+            cells: owners,
+            recolored: Vec::new(),
+        };
+        if self.graph_type == GraphType::Spark {
+            // The blocks at the top and bottom already show that a value is clipped
+            self.overflow.clear();
+            for (g, text) in spark_values.iter().enumerate() {
+                for (j, c) in text.chars().enumerate() {
+                    self.draw_exact(self.draw_width + j, g, GraphPixel::Normal(Pixel::Char(c)));
+                }
             }
         }
-        out
+        self.draw_thresholds(min_y, max_y);
+        self.draw_overflow();
+        self.draw_annotations(&annotations, &ranges);
+        if self.edge_value && self.enable_axis && self.graph_type != GraphType::Spark {
+            self.draw_edge_values(&edge_values, &ranges);
+        }
+        if self.enable_axis && self.graph_type != GraphType::Spark {
+            self.draw_grid();
+        }
+        self.draw_frame(frame);
+        if self.footer {
+            self.draw_footer(footer_rows);
+        }
+
+        Ok(())
     }
 
-    // Method that takes a closure to decide which GraphPixel variant to create
-    // A more customizable variant of the color! macro
-    fn color_pixel<F>(&self, px: Pixel, creator: F) -> GraphPixel<Pixel>
-    where
-        F: FnOnce(Pixel) -> GraphPixel<Pixel>,
-    {
-        if self.enable_color {
-            creator(px)
-        } else {
-            GraphPixel::Normal(px)
-        }
+    /// Build the graph as a SVG image, see try_build_svg
+    pub fn build_svg(&mut self) -> String {
+        self.try_build_svg().unwrap_or_default()
     }
 
-    /// Set a pixel at a absolute position in the canvas
-    ///
-    /// # Argument
-    ///
-    /// * `x` - X-position of pixel
-    /// * `y` - Y-position of pixel
-    /// * `px` - The pixel to set
-    fn draw_exact(&mut self, x: usize, y: usize, px: GraphPixel<Pixel>) {
-        let pos = y * self.width + x;
-        self.elements[pos] = px;
+    /// Build the graph as a SVG image, with the same layout, ticks, labels and colors as
+    /// try_build. The series are drawn as lines, points, bars and areas instead of characters,
+    /// everything else is drawn as in the text graph.
+    pub fn try_build_svg(&mut self) -> Result<String, GraphError> {
+        Ok(self.render()?.svg())
     }
 
-    /// Set a pixel in the drawable part of the canvas
-    ///
-    /// # Argument
-    ///
-    /// * `x` - Relative X-position of pixel
-    /// * `y` - Relative Y-position of pixel
-    /// * `px` - The pixel to set
-    fn draw(&mut self, x: usize, y: usize, px: GraphPixel<Pixel>) {
-        let pos = (y + self.row_offset) * self.width + (x + self.col_offset);
-        self.elements[pos] = px;
+    /// The graph drawn by draw_graph as a SVG image, see try_build_svg
+    fn svg(&self) -> String {
+        let mut svg = Svg::new(self.width, self.height);
+        svg.cells(&self.canvas, |x, y| self.is_plot_cell(x, y));
+        for g in 0..self.plot.levels.len() {
+            let color = svg::color(&self.series_pixel(g, Pixel::Char(' ')));
+            self.draw_svg_series(&mut svg, g, color);
+        }
+        // The series of the left y-axis are drawn again in the color of each threshold,
+        // only in the cells try_build recolored
+        for (color, cells) in &self.plot.recolored {
+            if cells.is_empty() {
+                continue;
+            }
+            let cells: Vec<(usize, usize)> = cells
+                .iter()
+                .map(|(x, y)| (x + self.col_offset, y + self.row_offset))
+                .collect();
+            let color = svg::color(&self.color_pixel(Pixel::Char(' '), color.pixel()));
+            svg.clip(&cells);
+            for g in 0..self.plot.levels.len() {
+                if self.series_axes[g] == YAxis::Left {
+                    self.draw_svg_series(&mut svg, g, color);
+                }
+            }
+            svg.end_clip();
+        }
+        svg.to_string()
     }
 
-    /// Add axis to the canvas and move graph drawing area inside axis
+    /// Build the graph, and return the statistics of each series with it, see build and stats
+    pub fn build_with_stats(&mut self) -> (String, Vec<Stats>) {
+        let graph = self.build();
+        (graph, self.stats.clone())
+    }
+
+    /// Write the statistics of each series under the graph, one line for each series.
+    /// The line starts with the name of the series, if there is more than one series.
     ///
     /// # Arguments
     ///
+    /// * `rows` - The number of rows at the bottom of the canvas kept for the footer
+    fn draw_footer(&mut self, rows: usize) {
+        let first_row = self.height - rows;
+        for g in 0..rows.min(self.stats.len()) {
+            let name = if self.stats.len() > 1 && !self.series_names[g].is_empty() {
+                format!("{}: ", self.series_names[g])
+            } else {
+                String::new()
+            };
+            let name = truncate(&name, self.width);
+            let name_width = display_width(&name);
+            let summary = self.stats[g].summary(self.precision, self.width - name_width);
+            let color = self.series_color(g);
+            self.canvas.text(0, first_row + g, &name, color);
+            self.write_text(name_width, first_row + g, &summary);
+        }
+    }
+
+    /// Keep rows and columns around the graph for the title, the axis labels, the legend and the footer,
+    /// and make the canvas the graph is drawn on smaller to match. Returns the number of rows
+    /// and columns kept on each side of the graph.
+    /// The parts that do not fit, while leaving room for a small graph, are left out.
+    fn reserve_frame(&mut self) -> Frame {
+        let mut frame = Frame::default();
+        if self.graph_type == GraphType::Spark {
+            // The footer of a spark graph is added under the sparklines
+            if self.footer {
+                frame.footer = self.y_values.len();
+            }
+            return frame;
+        }
+        // Room for the labels of both y-axes, the borders and ten columns of graph,
+        // the frame is left out before the graph gets narrower than that
+        let min_width = si_width(self.precision) + self.right_gutter() + 12;
+        let min_height = 3;
+        // The rows at the bottom of the canvas are kept for the footer, if there is room for them
+        if self.footer && self.height > self.y_values.len() {
+            frame.footer = self.y_values.len();
+        }
+        let height = self.height - frame.footer;
+        if self.title.is_some() && height > min_height {
+            frame.top = 1;
+        }
+        if self.x_label.is_some() && height - frame.top > min_height {
+            frame.bottom = 1;
+        }
+        // The widest character of a vertical label, and a space
+        let label_columns = |label: &String| label.chars().map(char_width).max().unwrap_or(0) + 1;
+        if let Some(label) = &self.y_label {
+            let columns = label_columns(label);
+            if self.width >= min_width + columns {
+                frame.left = columns;
+            }
+        }
+        if let (true, Some(label)) = (self.has_right_axis(), &self.right_y_label) {
+            // The right y-axis labels need as much room as the left ones
+            let columns = label_columns(label);
+            if self.width >= min_width + frame.left + columns {
+                frame.right_label = columns;
+            }
+        }
+        if self.legend {
+            // A space, both borders, the glyph and a space before the name
+            let columns = 5 + self
+                .legend_names()
+                .iter()
+                .map(|name| display_width(name))
+                .max()
+                .unwrap_or(0);
+            let rows = self.y_values.len() + 2;
+            if self.width >= min_width + frame.left + frame.right_label + columns
+                && height - frame.top - frame.bottom >= rows
+            {
+                frame.legend = columns;
+            }
+        }
+
+        self.width -= frame.left + frame.right_label + frame.legend;
+        self.height = height - frame.top - frame.bottom;
+        self.canvas = Canvas::new(self.width, self.height);
+        self.draw_width = self.width;
+        self.draw_height = self.height;
+        frame
+    }
+
+    /// The names of the series in the legend, shortened to a quarter of the width of the canvas
+    fn legend_names(&self) -> Vec<String> {
+        let max_width = (self.width / 4).max(1);
+        (0..self.y_values.len())
+            .map(|g| match self.series_names.get(g) {
+                Some(name) if !name.is_empty() => truncate(name, max_width),
+                _ => format!("{}", g + 1),
+            })
+            .collect()
+    }
+
+    /// The character a series is drawn with, as shown in the legend
+    fn legend_glyph(&self, g: usize) -> char {
+        match &self.graph_type {
+            GraphType::Star => SERIES_STARS[g % SERIES_STARS.len()],
+            GraphType::Ascii => ASCII_0,
+            GraphType::Braille(_) | GraphType::Scatter | GraphType::Area(AreaFill::Braille) => {
+                brc(0xFF)
+            }
+            GraphType::Area(AreaFill::Glyph(c)) => *c,
+            _ => BAR_UP[7],
+        }
+    }
+
+    /// Put the graph back in the canvas it was made smaller from by reserve_frame,
+    /// and write the title, the axis labels and the legend around it
+    ///
+    /// # Arguments
+    ///
+    /// * `frame` - The rows and columns around the graph, as returned by reserve_frame
+    fn draw_frame(&mut self, frame: Frame) {
+        let Frame {
+            top,
+            bottom,
+            left,
+            right_label,
+            legend,
+            footer,
+        } = frame;
+        let right = right_label + legend;
+        self.canvas.expand(top, bottom + footer, left, right);
+        self.width = self.canvas.width();
+        self.height = self.canvas.height();
+        self.col_offset += left;
+        self.row_offset += top;
+
+        if let (true, Some(title)) = (top > 0, &self.title) {
+            let title = truncate(title, self.width);
+            let start = (self.width - display_width(&title)) / 2;
+            self.write_text(start, 0, &title);
+        }
+        if let (true, Some(label)) = (bottom > 0, &self.x_label) {
+            let label = truncate(label, self.width);
+            let label_width = display_width(&label);
+            let center = self.col_offset + self.draw_width / 2;
+            let start = center
+                .saturating_sub(label_width / 2)
+                .min(self.width - label_width);
+            self.write_text(start, self.height - footer - 1, &label);
+        }
+        if let (true, Some(label)) = (left > 0, self.y_label.clone()) {
+            self.write_vertical(0, &label);
+        }
+        if let (true, Some(label)) = (right_label > 0, self.right_y_label.clone()) {
+            self.write_vertical(self.width - right + 1, &label);
+        }
+        if legend > 0 {
+            self.draw_legend(self.width - legend + 1, top);
+        }
+    }
+
+    /// Write a text from top to bottom, centered on the rows of the graph.
+    /// The characters that do not fit are left out.
+    ///
+    /// # Arguments
+    ///
+    /// * `x` - Column to write the text in
+    /// * `text` - The text to write
+    fn write_vertical(&mut self, x: usize, text: &str) {
+        let glyphs: Vec<String> = cells(text)
+            .into_iter()
+            .filter(|cell| !cell.is_empty())
+            .collect();
+        let count = glyphs.len().min(self.draw_height);
+        let start = self.row_offset + (self.draw_height - count) / 2;
+        for (j, glyph) in glyphs.iter().take(count).enumerate() {
+            self.write_text(x, start + j, glyph);
+        }
+    }
+
+    /// Draw the legend box, with the name of each series next to how it is drawn
+    ///
+    /// # Arguments
+    ///
+    /// * `x` - Column of the left border of the box
+    /// * `y` - Row of the top border of the box
+    fn draw_legend(&mut self, x: usize, y: usize) {
+        let names = self.legend_names();
+        let inner = 2 + names
+            .iter()
+            .map(|name| display_width(name))
+            .max()
+            .unwrap_or(0);
+        self.canvas
+            .rect(x, y, inner + 2, names.len() + 2, GraphPixel::Normal);
+        for (g, name) in names.iter().enumerate() {
+            let row = y + g + 1;
+            let glyph = self.series_pixel(g, Pixel::Char(self.legend_glyph(g)));
+            self.draw_exact(x + 1, row, glyph);
+            self.write_text(x + 3, row, name);
+        }
+    }
+
+    /// Write text at a absolute position in the canvas, measured by display width,
+    /// text outside the canvas is left out
+    ///
+    /// # Arguments
+    ///
+    /// * `x` - X-position of the first column of the text
+    /// * `y` - Y-position of the text
+    /// * `text` - The text to write
+    fn write_text(&mut self, x: usize, y: usize, text: &str) {
+        self.canvas.text(x, y, text, GraphPixel::Normal);
+    }
+
+    /// Give each series a row of its own, and leave room for the values after the sparklines
+    /// if they are enabled and fit
+    fn spark_layout(&mut self) {
+        self.height = self.y_values.len();
+        self.canvas = Canvas::new(self.width, self.height);
+        self.draw_height = self.height;
+        self.col_offset = 0;
+        self.row_offset = 0;
+        self.draw_width = self.width;
+        if self.spark_values {
+            // The sparkline keeps at least half of the width,
+            // the values are left out if not even the smallest one fits in the rest
+            let text_width = self.spark_values_width().min(self.width / 2);
+            if text_width >= " min ".len() + si_width(self.precision) {
+                self.draw_width = self.width - text_width;
+            } else {
+                self.spark_values = false;
+            }
+        }
+    }
+
+    /// The widest the text written by spark_values_text can be, this does not depend on
+    /// the values, so the sparkline keeps its width when the values change
+    fn spark_values_width(&self) -> usize {
+        " min  max  last ".len() + 3 * si_width(self.precision)
+    }
+
+    /// The min, max and last value of each series, written after the sparklines.
+    /// Values are left out from the end if they do not fit right of the sparklines.
+    fn spark_values_text(&self) -> Vec<String> {
+        let width = self.width - self.draw_width;
+        self.y_values
+            .iter()
+            .map(|values| {
+                let mut finite = values.iter().cloned().filter(|y| y.is_finite());
+                let min = finite.clone().fold(f64::INFINITY, f64::min);
+                let max = finite.clone().fold(f64::NEG_INFINITY, f64::max);
+                let last = finite.next_back().unwrap_or(f64::NAN);
+                let mut text = String::new();
+                for (name, value) in [("min", min), ("max", max), ("last", last)] {
+                    let field = format!(" {} {}", name, format_si(value, self.precision));
+                    if display_width(&text) + display_width(&field) > width {
+                        break;
+                    }
+                    text.push_str(&field);
+                }
+                text
+            })
+            .collect()
+    }
+
+    /// Replace the values of each series by the number of values in each bin of a histogram,
+    /// all series share the same bins.
+    /// There are never more bins than there is room for columns.
+    fn bin(&mut self) {
+        let Some(binning) = &self.binning else {
+            return;
+        };
+        let max_bins = if self.enable_axis && self.graph_type != GraphType::Spark {
+            self.width
+                .saturating_sub(2 + si_width(self.precision) + self.right_gutter())
+        } else {
+            self.width
+        };
+        let all_values: Vec<f64> = self.y_values.iter().flatten().cloned().collect();
+        let edges = bin_edges(&all_values, binning, max_bins);
+        for values in self.y_values.iter_mut() {
+            *values = bin_counts(values, &edges);
+        }
+        self.x_values = edges.windows(2).map(|w| (w[0] + w[1]) / 2.0).collect();
+        self.bin_edges = Some(edges);
+    }
+
+    /// Spread the bins of a histogram over the columns of the graph,
+    /// so each bin is drawn as a bar as wide as possible
+    fn spread_bins(&mut self) {
+        let columns = self.draw_width * self.samples_per_column();
+        for values in self.y_values.iter_mut() {
+            if values.is_empty() {
+                continue;
+            }
+            *values = (0..columns)
+                .map(|c| values[c * values.len() / columns])
+                .collect();
+        }
+        let bins = self.x_values.len().max(1);
+        self.x_values = (0..columns)
+            .map(|c| {
+                self.x_values
+                    .get(c * bins / columns)
+                    .cloned()
+                    .unwrap_or(0.0)
+            })
+            .collect();
+    }
+
+    /// Whether any series is drawn against the right y-axis
+    fn has_right_axis(&self) -> bool {
+        self.series_axes
+            .iter()
+            .take(self.y_values.len())
+            .any(|axis| *axis == YAxis::Right)
+    }
+
+    /// The level bars and areas grow from, zero if it is on the y-axis, otherwise the bottom
+    /// of the graph. The baseline is rounded to a whole row, so bars both above and below it
+    /// can be drawn with the available blocks. Zero is already at the bottom of a row,
+    /// see bar_range, unless both bounds of the y-axis are fixed.
+    ///
+    /// # Arguments
+    ///
+    /// * `range` - The range of the y-axis the bars are drawn against
+    fn bar_baseline(&self, (min_y, max_y): (f64, f64)) -> usize {
+        if self.y_scale != YScale::Linear || min_y > 0.0 || max_y < 0.0 {
+            return 0;
+        }
+        let level = scale_level(0.0, (min_y, max_y), self.scale_levels()).round() as usize;
+        (level + self.rows_per_cell() / 2) / self.rows_per_cell() * self.rows_per_cell()
+    }
+
+    /// How many levels the values of a bar graph are moved, so zero is exactly on the baseline
+    /// and zero values get no bar. This is only more than a rounding error if both bounds of
+    /// the y-axis are fixed, see bar_range.
+    ///
+    /// # Arguments
+    ///
+    /// * `range` - The range of the y-axis the bars are drawn against
+    fn bar_shift(&self, (min_y, max_y): (f64, f64)) -> f64 {
+        let bars = matches!(
+            self.graph_type,
+            GraphType::Bar | GraphType::Area(AreaFill::Block)
+        );
+        if !bars || self.y_scale != YScale::Linear || min_y > 0.0 || max_y < 0.0 {
+            return 0.0;
+        }
+        let zero = scale_level(0.0, (min_y, max_y), self.scale_levels());
+        self.bar_baseline((min_y, max_y)) as f64 - zero
+    }
+
+    /// Widen a range of the y-axis of a bar graph, so zero is at the bottom of a row.
+    /// The bars then grow from exactly zero, both upwards and downwards, and zero gets a tick
+    /// on the row the bars grow from. The bounds of the range that are fixed are kept.
+    ///
+    /// # Arguments
+    ///
+    /// * `range` - The range of the y-axis
+    /// * `fixed` - Whether the bottom and the top of the range are fixed
+    fn bar_range(
+        &self,
+        (min_y, max_y): (f64, f64),
+        (fixed_min, fixed_max): (bool, bool),
+    ) -> (f64, f64) {
+        let bars = matches!(
+            self.graph_type,
+            GraphType::Bar | GraphType::Area(AreaFill::Block)
+        );
+        let rows = self.axis_draw_height() as f64;
+        if !bars || self.y_scale != YScale::Linear || min_y >= 0.0 || max_y <= 0.0 || rows < 2.0 {
+            return (min_y, max_y);
+        }
+        // The part of the range below zero
+        let below = fraction(0.0, min_y, max_y);
+        let rows_below = match (fixed_min, fixed_max) {
+            (false, false) => (below * rows).round(),
+            (false, true) => (below * rows).ceil(),
+            (true, false) => (below * rows).floor(),
+            (true, true) => return (min_y, max_y),
+        }
+        .clamp(1.0, rows - 1.0);
+        let range = if rows_below / rows > below && !fixed_min {
+            (-max_y * rows_below / (rows - rows_below), max_y)
+        } else if rows_below / rows < below && !fixed_max {
+            (min_y, -min_y * (rows - rows_below) / rows_below)
+        } else {
+            (min_y, max_y)
+        };
+        if range.0.is_finite() && range.1.is_finite() {
+            range
+        } else {
+            (min_y, max_y)
+        }
+    }
+
+    /// Decide the range of one of the y-axes, from the series drawn against it.
+    /// The thresholds, the sticky range and the fixed bounds only apply to the left y-axis.
+    /// The range of a bar graph is widened to put zero at the bottom of a row, see bar_range.
+    ///
+    /// # Arguments
+    ///
+    /// * `axis` - The y-axis to find the range of
+    fn find_y_range(&self, axis: &YAxis) -> (f64, f64) {
+        let values = self
+            .y_values
+            .iter()
+            .zip(self.series_axes.iter())
+            .filter(|(_, series_axis)| *series_axis == axis)
+            .flat_map(|(values, _)| values.iter().cloned());
+        let mut min_y = values.clone().fold(f64::INFINITY, f64::min);
+        let mut max_y = values.fold(f64::NEG_INFINITY, f64::max);
+
+        if self.include_zero && self.y_scale == YScale::Linear {
+            min_y = min_y.min(0.0);
+            max_y = max_y.max(0.0);
+        }
+        if *axis == YAxis::Right {
+            return self.bar_range(padded_range(min_y, max_y, None, None), (false, false));
+        }
+        for (threshold, _) in &self.thresholds {
+            let threshold = self.y_scale.apply(*threshold);
+            if threshold.is_finite() {
+                min_y = min_y.min(threshold);
+                max_y = max_y.max(threshold);
+            }
+        }
+
+        if let Some(((previous_min, previous_max), decay)) = self.sticky {
+            let previous_min = self.y_scale.apply(previous_min);
+            let previous_max = self.y_scale.apply(previous_max);
+            if previous_min.is_finite() && previous_min < min_y {
+                min_y = previous_min + (min_y - previous_min) * decay;
+            }
+            if previous_max.is_finite() && previous_max > max_y {
+                max_y = previous_max + (max_y - previous_max) * decay;
+            }
+        }
+
+        let y_min = self
+            .y_min
+            .map(|y| self.y_scale.apply(y))
+            .filter(|y| !y.is_nan());
+        let y_max = self
+            .y_max
+            .map(|y| self.y_scale.apply(y))
+            .filter(|y| !y.is_nan());
+        let range = padded_range(y_min.unwrap_or(min_y), y_max.unwrap_or(max_y), y_min, y_max);
+        self.bar_range(range, (y_min.is_some(), y_max.is_some()))
+    }
+
+    /// How many samples are drawn in each column of the canvas
+    fn samples_per_column(&self) -> usize {
+        match self.graph_type.sub_cell() {
+            Some(grid) => grid.columns(),
+            None => 1,
+        }
+    }
+
+    /// How many different heights a sample can have within one row of the canvas
+    fn rows_per_cell(&self) -> usize {
+        if let Some(grid) = self.graph_type.sub_cell() {
+            return grid.rows();
+        }
+        match self.graph_type {
+            GraphType::Bar | GraphType::Spark | GraphType::Area(AreaFill::Block) => 8,
+            _ => 1,
+        }
+    }
+
+    /// How many different heights a sample can have in the draw area
+    fn scale_levels(&self) -> usize {
+        match self.graph_type {
+            // A bar can also have no height at all
+            GraphType::Bar | GraphType::Area(AreaFill::Block) => {
+                self.draw_height * self.rows_per_cell() + 1
+            }
+            // Each series has a row of its own
+            GraphType::Spark => self.rows_per_cell(),
+            _ => self.draw_height * self.rows_per_cell(),
+        }
+    }
+
+    /// Find the row of the draw area a value ends up in, counted from the bottom of the draw area,
+    /// this matches the scaling done in build.
+    ///
+    /// # Arguments
+    ///
+    /// * `value` - The value to find the row of
+    /// * `min_y` - The value of the bottom of the draw area
+    /// * `max_y` - The value of the top of the draw area
+    fn value_row(&self, value: f64, min_y: f64, max_y: f64) -> usize {
+        let scale_height = self.scale_levels();
+        if max_y <= min_y {
+            return 0;
+        }
+        let scaled = (scale_level(value, (min_y, max_y), scale_height)
+            + self.bar_shift((min_y, max_y)))
+        .round();
+        ((scaled.max(0.0) as usize).min(scale_height - 1) / self.rows_per_cell())
+            .min(self.draw_height - 1)
+    }
+
+    // Downsample using a common downsampling, this allows us to avoid doing anything
+    // with the x values.
+    // Make sure to only use one downsampling-algorithm
+    fn downsample_columns(&mut self) {
+        let scale_width = self.draw_width * self.samples_per_column();
+        self.y_lows.clear();
+        for g in 0..self.y_values.len() {
+            self.y_values[g] = match self.downsample {
+                Downsample::Nearest => downsample::nearest(&self.y_values[g], scale_width),
+                Downsample::Mean => downsample::mean(&self.y_values[g], scale_width),
+                Downsample::Lttb => downsample::lttb(&self.y_values[g], scale_width),
+                Downsample::MinMax => {
+                    let (min, max) = downsample::min_max(&self.y_values[g], scale_width);
+                    self.y_lows.push(min);
+                    max
+                }
+            };
+        }
+        // Keep the x values in step with the samples, so the x-axis labels match
+        self.x_values = downsample::nearest(&self.x_values, scale_width);
+    }
+
+    // Method that takes a closure to decide which GraphPixel variant to create
+    // A more customizable variant of the color! macro
+    fn color_pixel<F>(&self, px: Pixel, creator: F) -> GraphPixel<Pixel>
+    where
+        F: FnOnce(Pixel) -> GraphPixel<Pixel>,
+    {
+        if self.enable_color {
+            creator(px)
+        } else {
+            GraphPixel::Normal(px)
+        }
+    }
+
+    /// Create a pixel in the color of series `g`
+    fn series_pixel(&self, g: usize, px: Pixel) -> GraphPixel<Pixel> {
+        self.series_color(g)(px)
+    }
+
+    /// The GraphPixel variant series `g` is drawn with
+    fn series_color(&self, g: usize) -> fn(Pixel) -> GraphPixel<Pixel> {
+        if self.enable_color {
+            SERIES_COLORS[g % SERIES_COLORS.len()]
+        } else {
+            GraphPixel::Normal
+        }
+    }
+
+    /// Set a pixel at a absolute position in the canvas
+    ///
+    /// # Argument
+    ///
+    /// * `x` - X-position of pixel
+    /// * `y` - Y-position of pixel
+    /// * `px` - The pixel to set
+    fn draw_exact(&mut self, x: usize, y: usize, px: GraphPixel<Pixel>) {
+        self.canvas.set(x, y, px);
+    }
+
+    /// Set a pixel in the drawable part of the canvas
+    ///
+    /// # Argument
+    ///
+    /// * `x` - Relative X-position of pixel
+    /// * `y` - Relative Y-position of pixel
+    /// * `px` - The pixel to set
+    fn draw(&mut self, x: usize, y: usize, px: GraphPixel<Pixel>) {
+        self.canvas
+            .set(x + self.col_offset, y + self.row_offset, px);
+    }
+
+    /// The cells of the drawable part of the canvas, row by row
+    fn draw_area_cells(&self) -> Vec<GraphPixel<Pixel>> {
+        (0..self.draw_height)
+            .flat_map(|y| (0..self.draw_width).map(move |x| (x, y)))
+            .map(|(x, y)| {
+                self.canvas
+                    .get(x + self.col_offset, y + self.row_offset)
+                    .cloned()
+                    .unwrap_or_default()
+            })
+            .collect()
+    }
+
+    /// Whether nothing is drawn in a cell of the drawable part of the canvas
+    ///
+    /// # Argument
+    ///
+    /// * `x` - Relative X-position of the cell
+    /// * `y` - Relative Y-position of the cell
+    fn is_blank(&self, x: usize, y: usize) -> bool {
+        matches!(
+            self.canvas.get(x + self.col_offset, y + self.row_offset),
+            Some(GraphPixel::Blank)
+        )
+    }
+
+    /// Set a single braille or block dot in the drawable part of the canvas,
+    /// in the color of series `g`. Dots already set in the same character are kept.
+    ///
+    /// # Argument
+    ///
+    /// * `g` - The series the dot belongs to
+    /// * `x` - Relative X-position of the dot
+    /// * `y` - Relative Y-position of the dot, counted from the top
+    /// * `grid` - The dots inside each character
+    fn draw_dot(&mut self, g: usize, x: usize, y: usize, grid: &SubCell) {
+        let (x, y) = self.dot_position((x, y), grid);
+        self.canvas.dot(x, y, grid, self.series_color(g));
+    }
+
+    /// The position of a dot of the drawable part of the canvas, on the whole canvas
+    fn dot_position(&self, (x, y): (usize, usize), grid: &SubCell) -> (usize, usize) {
+        (
+            x + self.col_offset * grid.columns(),
+            y + self.row_offset * grid.rows(),
+        )
+    }
+
+    /// Number of columns right of the graph, used for the labels of edge_value
+    /// and the right y-axis
+    fn right_gutter(&self) -> usize {
+        if (self.edge_value || self.has_right_axis())
+            && self.enable_axis
+            && self.graph_type != GraphType::Spark
+        {
+            si_width(self.precision)
+        } else {
+            0
+        }
+    }
+
+    /// Find the smallest, largest and last value of each series, returns the series,
+    /// the label, the x value and the position on the y-axis of each of them.
+    /// A sample that is both the last and the smallest or largest only gets one label.
+    fn find_annotations(&self) -> Vec<(usize, String, f64, f64)> {
+        let mut annotations = Vec::new();
+        for (g, values) in self.y_values.iter().enumerate() {
+            let mut samples = (0..values.len().min(self.x_values.len()))
+                .filter(|i| !values[*i].is_nan() && self.x_values[*i].is_finite());
+            let Some(min) = samples
+                .clone()
+                .min_by(|a, b| values[*a].total_cmp(&values[*b]))
+            else {
+                continue;
+            };
+            let max = samples
+                .clone()
+                .max_by(|a, b| values[*a].total_cmp(&values[*b]))
+                .unwrap_or(min);
+            let last = samples.next_back().unwrap_or(min);
+            let mut labelled = Vec::new();
+            for (name, i) in [("max", max), ("min", min), ("last", last)] {
+                if labelled.contains(&i) {
+                    continue;
+                }
+                labelled.push(i);
+                let value = format_si(self.y_scale.invert(values[i]), self.precision);
+                annotations.push((
+                    g,
+                    format!("{} {}", name, value),
+                    self.x_values[i],
+                    values[i],
+                ));
+            }
+        }
+        annotations
+    }
+
+    /// Write the annotations from find_annotations next to their values.
+    /// A label is placed above, below, right or left of its value, in an order depending on the
+    /// kind of label, where it does not cover anything else. If there is no such place,
+    /// the label is left out.
+    ///
+    /// # Arguments
+    ///
+    /// * `annotations` - The annotations to write, see find_annotations
+    /// * `ranges` - The range of the y-axis of each series
+    fn draw_annotations(
+        &mut self,
+        annotations: &[(usize, String, f64, f64)],
+        ranges: &[(f64, f64)],
+    ) {
+        let columns: Vec<f64> = self
+            .x_values
+            .iter()
+            .step_by(self.samples_per_column())
+            .take(self.draw_width)
+            .cloned()
+            .collect();
+        for (g, label, x, y) in annotations {
+            let Some(column) = (0..columns.len())
+                .min_by(|a, b| (columns[*a] - x).abs().total_cmp(&(columns[*b] - x).abs()))
+            else {
+                return;
+            };
+            let (min_y, max_y) = ranges[*g];
+            let row = self.draw_height - self.value_row(*y, min_y, max_y) - 1;
+            let width = display_width(label);
+            if width > self.draw_width {
+                continue;
+            }
+            // Places as (first column, row), relative to the draw area
+            let centered = column
+                .saturating_sub(width / 2)
+                .min(self.draw_width - width);
+            let above = row.checked_sub(1).map(|row| (centered, row));
+            let below = Some((centered, row + 1));
+            let right = Some((column + 2, row));
+            let left = column.checked_sub(width + 1).map(|start| (start, row));
+            let places = if label.starts_with("max") {
+                [above, right, left, below]
+            } else if label.starts_with("min") {
+                [below, right, left, above]
+            } else {
+                [left, above, below, right]
+            };
+            let free = |(start, row): (usize, usize)| {
+                row < self.draw_height
+                    && start + width <= self.draw_width
+                    && (start..start + width).all(|x| self.is_blank(x, row))
+            };
+            if let Some((start, row)) = places.into_iter().flatten().find(|place| free(*place)) {
+                let color = self.series_color(*g);
+                self.canvas
+                    .text(start + self.col_offset, row + self.row_offset, label, color);
+            }
+        }
+    }
+
+    /// Write the last value of each series right of the graph, at the row of the value.
+    /// If two series end on the same row, or on the row of a label of the right y-axis,
+    /// the value is left out.
+    ///
+    /// # Arguments
+    ///
+    /// * `values` - The last value of each series, if it has one
+    /// * `ranges` - The range of the y-axis of each series
+    fn draw_edge_values(&mut self, values: &[Option<f64>], ranges: &[(f64, f64)]) {
+        let start = self.col_offset + self.draw_width + 1;
+        for (g, value) in values.iter().enumerate() {
+            let Some(value) = value else {
+                continue;
+            };
+            let (min_y, max_y) = ranges[g];
+            let row = self.row_offset + self.draw_height - self.value_row(*value, min_y, max_y) - 1;
+            if !matches!(self.canvas.get(start, row), Some(GraphPixel::Blank)) {
+                continue;
+            }
+            let label = format_si(self.y_scale.invert(*value), self.precision);
+            for (j, c) in label.chars().enumerate().take(self.width - start) {
+                let px = self.series_pixel(g, Pixel::Char(c));
+                self.draw_exact(start + j, row, px);
+            }
+        }
+    }
+
+    /// Find the ticks of a y-axis, returns the row on the canvas and the label of each tick.
+    /// Nice values are used for the ticks, at most one for each row.
+    ///
+    /// # Arguments
+    ///
+    /// * `min_y` - The value of the bottom of the graph
+    /// * `max_y` - The value of the top of the graph
+    fn y_ticks(&self, min_y: f64, max_y: f64) -> Vec<(usize, String)> {
+        let ticks: Vec<(f64, String)> = match self.y_scale.base() {
+            None => {
+                let ticks = nice_ticks(min_y, max_y, self.draw_height);
+                let step = if ticks.len() > 1 {
+                    ticks[1] - ticks[0]
+                } else {
+                    max_y - min_y
+                };
+                ticks
+                    .into_iter()
+                    .map(|tick| (tick, format_tick(tick, step, self.precision)))
+                    .collect()
+            }
+            Some(base) => log_ticks(min_y, max_y, base, self.draw_height)
+                .into_iter()
+                .map(|tick| (self.y_scale.apply(tick), format_si(tick, self.precision)))
+                .collect(),
+        };
+        let mut y_ticks: Vec<(usize, String)> = Vec::with_capacity(ticks.len());
+        for (position, label) in ticks {
+            let row = 1 + self.draw_height - 1 - self.value_row(position, min_y, max_y);
+            if y_ticks.last().is_some_and(|(last_row, _)| *last_row == row) {
+                continue;
+            }
+            y_ticks.push((row, label));
+        }
+        y_ticks
+    }
+
+    /// The rows of the canvas used by the axis and the graph inside it,
+    /// the bottom row is kept for the x-axis labels if there is room for it
+    fn axis_height(&self) -> usize {
+        if self.height > 4 {
+            self.height - 1
+        } else {
+            self.height
+        }
+    }
+
+    /// The height of the draw area once the axis has been drawn, see draw_axis
+    fn axis_draw_height(&self) -> usize {
+        if !self.enable_axis || self.graph_type == GraphType::Spark || self.draw_height <= 2 {
+            return self.draw_height;
+        }
+        self.axis_height() - 2
+    }
+
+    /// Add axis to the canvas and move graph drawing area inside axis
+    ///
+    /// # Arguments
+    ///
+    /// * `min_y` - The value of the bottom of the graph
+    /// * `max_y` - The value of the top of the graph
+    /// * `right_range` - The range of the right y-axis, if any series are drawn against it
     /// * `c1` - Horizontal axis lines
     /// * `c2` - Vertical axis lines
     /// * `c4` - Bottom left axis pixel
     /// * `c5` - Top left axis pixel
     /// * `c6` - Bottom right axis pixel
     /// * `c7` - Top right axis pixel
+    #[allow(clippy::too_many_arguments)]
     fn draw_axis(
         &mut self,
         min_y: f64,
         max_y: f64,
+        right_range: Option<(f64, f64)>,
         c1: GraphPixel<Pixel>,
         c2: GraphPixel<Pixel>,
         c3: GraphPixel<Pixel>,
@@ -428,195 +1846,1607 @@ impl GraphBuilder {
         c5: GraphPixel<Pixel>,
         c6: GraphPixel<Pixel>,
     ) {
-        let mut y_ticks: Vec<String> = Vec::with_capacity(self.height);
-        let mut x_offset: usize = 0;
-        for i in 0..self.height {
-            let n = (min_y + (((max_y - min_y) / (self.height as f64 - 1.0)) * i as f64))
-                .round()
-                .to_string();
-            if n.len() > x_offset {
-                x_offset = n.len();
-            }
-            y_ticks.insert(0, n);
+        let axis_height = self.axis_height();
+        if axis_height < self.height {
+            self.x_label_row = Some(axis_height);
         }
+        self.draw_height = self.axis_draw_height();
 
-        for i in 0..self.height {
-            self.elements[i * self.width + x_offset] = c1.clone();
-            self.elements[i * self.width + self.width - 1] = c1.clone();
-            for (j, c) in y_ticks[i].chars().enumerate() {
-                self.elements[i * self.width + j] = GraphPixel::Normal(Pixel::Char(c));
-            }
+        let mut y_ticks = self.y_ticks(min_y, max_y);
+        let mut right_ticks = match right_range {
+            Some((min_y, max_y)) => self.y_ticks(min_y, max_y),
+            None => Vec::new(),
+        };
+
+        // The gutter is made wide enough for any label of the chosen precision,
+        // so it does not change width between redraws
+        let mut x_offset = y_ticks
+            .iter()
+            .map(|(_, label)| display_width(label))
+            .fold(si_width(self.precision), usize::max);
+        let mut right_width = right_ticks
+            .iter()
+            .map(|(_, label)| display_width(label))
+            .fold(self.right_gutter(), usize::max);
+        // The labels are left out if there is no room for both borders and a column of graph,
+        // first the labels right of the graph, then the y-axis labels
+        if x_offset + 3 + right_width > self.width {
+            self.edge_value = false;
+            right_ticks.clear();
+            right_width = 0;
+        }
+        self.y_tick_rows = y_ticks.iter().map(|(row, _)| *row).collect();
+        self.right_tick_rows = right_ticks.iter().map(|(row, _)| *row).collect();
+        if x_offset + 3 > self.width {
+            x_offset = 0;
+            y_ticks.clear();
         }
-        for i in 1 + x_offset..self.width - 1 {
-            self.elements[i] = c2.clone();
-            self.elements[(self.height - 1) * self.width + i] = c2.clone();
+        let right = self.width - 1 - right_width;
+
+        let bottom = axis_height - 1;
+        self.canvas
+            .line((x_offset, 0), (x_offset, bottom), c1.clone());
+        self.canvas.line((right, 0), (right, bottom), c1);
+        for (row, label) in y_ticks.iter() {
+            self.write_text(x_offset - display_width(label), *row, label);
+        }
+        for (row, label) in right_ticks.iter() {
+            self.write_text(right + 1, *row, label);
         }
-        self.elements[x_offset] = c4.clone();
-        self.elements[self.width - 1] = c6.clone();
-        self.elements[(self.height - 1) * self.width + x_offset] = c3.clone();
-        self.elements[self.height * self.width - 1] = c5.clone();
-        if self.draw_height > 2 {
-            self.draw_height = self.height - 2;
+        if right > x_offset + 1 {
+            self.canvas
+                .line((x_offset + 1, 0), (right - 1, 0), c2.clone());
+            self.canvas
+                .line((x_offset + 1, bottom), (right - 1, bottom), c2);
         }
+        self.draw_exact(x_offset, 0, c4);
+        self.draw_exact(right, 0, c6);
+        self.draw_exact(x_offset, bottom, c3);
+        self.draw_exact(right, bottom, c5);
         if self.draw_width > 2 {
-            self.draw_width = self.width - 2 - x_offset;
+            self.draw_width = right - 1 - x_offset;
         }
         self.col_offset = x_offset + 1;
         self.row_offset = 1;
     }
 
+    /// Draw the gridlines in the blank cells of the graph, and the tick marks on the border
+    /// of the axis where they meet it, see grid
+    fn draw_grid(&mut self) {
+        let (left, right) = (self.col_offset - 1, self.col_offset + self.draw_width);
+        let (top, bottom) = (self.row_offset - 1, self.row_offset + self.draw_height);
+        // Lines right next to a border of the axis are left out, they would look like a border
+        let rows: Vec<usize> = self
+            .y_tick_rows
+            .iter()
+            .cloned()
+            .filter(|row| self.horizontal_grid && *row > top + 1 && *row + 1 < bottom)
+            .collect();
+        let columns: Vec<usize> = self
+            .x_tick_columns
+            .iter()
+            .cloned()
+            .filter(|x| self.vertical_grid && *x > left + 1 && *x + 1 < right)
+            .collect();
+
+        for y in top + 1..bottom {
+            for x in left + 1..right {
+                let c = match (rows.contains(&y), columns.contains(&x)) {
+                    (true, true) => '┼',
+                    (true, false) => ASCII_0,
+                    (false, true) => ASCII_1,
+                    (false, false) => continue,
+                };
+                if matches!(self.canvas.get(x, y), Some(GraphPixel::Blank)) {
+                    let px = self.color_pixel(Pixel::Char(c), GraphPixel::Dim);
+                    self.draw_exact(x, y, px);
+                }
+            }
+        }
+
+        // Only plain borders get tick marks, not the first and last values of draw_ascii
+        // The ticks of the right y-axis point out of the graph, towards their labels
+        let right_rows: Vec<usize> = self
+            .right_tick_rows
+            .iter()
+            .cloned()
+            .filter(|row| self.horizontal_grid && *row > top && *row < bottom)
+            .collect();
+        let right_marks =
+            (top + 1..bottom).filter_map(|y| match (rows.contains(&y), right_rows.contains(&y)) {
+                (true, true) => Some((right, y, ASCII_1, '┼')),
+                (true, false) => Some((right, y, ASCII_1, '┤')),
+                (false, true) => Some((right, y, ASCII_1, '├')),
+                (false, false) => None,
+            });
+        let marks = rows
+            .iter()
+            .map(|y| (left, *y, ASCII_1, '┼'))
+            .chain(right_marks)
+            .chain(
+                columns
+                    .iter()
+                    .flat_map(|x| [(*x, top, ASCII_0, '┬'), (*x, bottom, ASCII_0, '┼')]),
+            );
+        for (x, y, border, mark) in marks.collect::<Vec<_>>() {
+            if matches!(self.canvas.get(x, y), Some(GraphPixel::Normal(Pixel::Char(c))) if *c == border)
+            {
+                self.draw_exact(x, y, GraphPixel::Normal(Pixel::Char(mark)));
+            }
+        }
+    }
+
+    /// Make the graph fit small canvases, by leaving out the axis if there is no room for
+    /// its borders, and drawing sparklines if there is at most one row for each series.
+    /// The labels of the axis are left out by draw_axis, if needed.
+    fn fit_layout(&mut self) {
+        if self.graph_type == GraphType::Spark {
+            return;
+        }
+        if self.width < 3 || self.height < 3 {
+            self.enable_axis = false;
+        }
+        if !self.enable_axis && self.height <= self.y_values.len() {
+            // Only the series that have a row of their own are drawn
+            self.graph_type = GraphType::Spark;
+            self.y_values.truncate(self.height);
+        }
+    }
+
+    /// Write labels for nice x values under the x-axis.
+    /// As many labels as possible are used, with at least two spaces between them.
+    /// This assumes the x values have been resampled to match the y values.
+    fn draw_x_ticks(&mut self) {
+        let Some(row) = self.x_label_row else {
+            return;
+        };
+        let columns: Vec<f64> = self
+            .x_values
+            .iter()
+            .step_by(self.samples_per_column())
+            .take(self.draw_width)
+            .cloned()
+            .collect();
+        if columns.is_empty() {
+            return;
+        }
+
+        let mut max_count = columns.len().div_ceil(2);
+        let ticks = loop {
+            let ticks = self.place_x_ticks(&columns, max_count);
+            if ticks.is_some() || max_count == 1 {
+                break ticks;
+            }
+            max_count -= 1;
+        };
+
+        let ticks = ticks.unwrap_or_default();
+        self.x_tick_columns = ticks.iter().map(|(column, _, _)| *column).collect();
+        for (_, start, label) in ticks {
+            for (j, c) in label.chars().enumerate() {
+                self.draw_exact(start + j, row, GraphPixel::Normal(Pixel::Char(c)));
+            }
+        }
+    }
+
+    /// Find where to write the labels of at most `max_count` x-axis ticks, returns the column
+    /// of each tick and the start position of its label in the canvas, or None if they would overlap
+    ///
+    /// # Arguments
+    ///
+    /// * `columns` - The x value of each column of the graph
+    /// * `max_count` - The maximum number of labels to place
+    fn place_x_ticks(
+        &self,
+        columns: &[f64],
+        max_count: usize,
+    ) -> Option<Vec<(usize, usize, String)>> {
+        let mut labels: Vec<(usize, usize, String)> = Vec::with_capacity(max_count);
+        for (column, label) in self.x_tick_labels(columns, max_count) {
+            let label_width = display_width(&label);
+            if label_width > self.width {
+                return None;
+            }
+
+            // Center the label under its column, but keep it inside the canvas
+            let center = self.col_offset + column;
+            let start = center
+                .saturating_sub(label_width / 2)
+                .min(self.width - label_width);
+            if let Some((_, last_start, last_label)) = labels.last() {
+                if last_start + display_width(last_label) + 2 > start {
+                    return None;
+                }
+            }
+            labels.push((center, start, label));
+        }
+        Some(labels)
+    }
+
+    /// Find at most `max_count` x-axis ticks, returns the column and label of each tick.
+    /// The ticks are nice values, a histogram also has a tick at its outer bin edges.
+    ///
+    /// # Arguments
+    ///
+    /// * `columns` - The x value of each column of the graph
+    /// * `max_count` - The maximum number of ticks
+    fn x_tick_labels(&self, columns: &[f64], max_count: usize) -> Vec<(usize, String)> {
+        if let Some(edges) = &self.bin_edges {
+            if edges.len() < 2 {
+                return Vec::new();
+            }
+            let (first, last) = (edges[0], edges[edges.len() - 1]);
+            let interior = if max_count > 2 {
+                nice_ticks(first, last, max_count - 2)
+            } else {
+                Vec::new()
+            };
+            let step = if interior.len() > 1 {
+                interior[1] - interior[0]
+            } else {
+                half_span(first, last) / (edges.len() - 1) as f64 * 2.0
+            };
+            let ends = [
+                (0, format_tick(first, step, self.precision)),
+                (columns.len(), format_tick(last, step, self.precision)),
+            ];
+
+            // The outer edges are always labeled, the nice values between them only
+            // if their label fits next to the labels of the outer edges
+            let fits = |column: usize, label: &str| {
+                ends.iter().all(|(end, end_label)| {
+                    column.abs_diff(*end) * 2 >= display_width(label) + display_width(end_label) + 4
+                })
+            };
+            let mut ticks = vec![ends[0].clone()];
+            ticks.extend(
+                interior
+                    .into_iter()
+                    .filter(|tick| *tick > first && *tick < last)
+                    .map(|tick| {
+                        let column = (fraction(tick, first, last) * columns.len() as f64).round();
+                        (column as usize, format_tick(tick, step, self.precision))
+                    })
+                    .filter(|(column, label)| fits(*column, label)),
+            );
+            ticks.push(ends[1].clone());
+            return ticks;
+        }
+
+        let first = columns[0];
+        let last_column = columns.len() - 1;
+        let span = columns[last_column] - first;
+        let ticks = match self.x_format {
+            XFormat::Number => nice_ticks(first, columns[last_column], max_count),
+            XFormat::Time => time_ticks(first, columns[last_column], max_count),
+        };
+        let step = if ticks.len() > 1 {
+            ticks[1] - ticks[0]
+        } else {
+            span
+        };
+        ticks
+            .into_iter()
+            .map(|tick| {
+                let column = if span > 0.0 {
+                    ((tick - first) / span * last_column as f64).round() as usize
+                } else {
+                    0
+                };
+                let label = match self.x_format {
+                    XFormat::Number => format_tick(tick, step, self.precision),
+                    XFormat::Time => format_time(tick, span),
+                };
+                (column, label)
+            })
+            .collect()
+    }
+
+    /// Give everything the series of the left y-axis drew above each threshold the color
+    /// of the threshold, and draw the threshold lines where nothing else is drawn.
+    /// Lower thresholds are handled first, so the highest threshold below a sample decides
+    /// its color. For sparklines, the level of each block is compared with the threshold
+    /// instead, and no line is drawn.
+    ///
+    /// # Arguments
+    ///
+    /// * `min_y` - The value of the bottom of the draw area
+    /// * `max_y` - The value of the top of the draw area
+    fn draw_thresholds(&mut self, min_y: f64, max_y: f64) {
+        let mut thresholds: Vec<(f64, Color)> = self
+            .thresholds
+            .iter()
+            .map(|(value, color)| (self.y_scale.apply(*value), color.clone()))
+            .filter(|(value, _)| *value >= min_y && *value <= max_y)
+            .collect();
+        thresholds.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+        let mut lines = Vec::new();
+        for (value, color) in thresholds {
+            // The cells above the threshold, or the blocks above it for sparklines
+            let mut above = Vec::new();
+            if self.graph_type == GraphType::Spark {
+                let levels = self.scale_levels();
+                let level = scale_level(value, (min_y, max_y), levels).round();
+                for (g, values) in self.y_values.iter().enumerate() {
+                    if self.series_axes[g] != YAxis::Left {
+                        continue;
+                    }
+                    for (i, y) in values.iter().enumerate().take(self.draw_width) {
+                        if *y > level {
+                            above.push((i, g));
+                        }
+                    }
+                }
+            } else {
+                let row = self.draw_height - self.value_row(value, min_y, max_y) - 1;
+                for y in 0..row {
+                    for x in 0..self.draw_width {
+                        // The thresholds are values on the left y-axis
+                        let owner = self.plot.cells[y * self.draw_width + x];
+                        if owner.is_some_and(|g| self.series_axes[g] == YAxis::Left) {
+                            above.push((x, y));
+                        }
+                    }
+                }
+                lines.push((row, color.clone()));
+            }
+            let mut recolored = Vec::new();
+            for (x, y) in above {
+                let cell = self.canvas.get(x + self.col_offset, y + self.row_offset);
+                if let Some(px) = cell.and_then(|px| px.inner()) {
+                    let px = self.color_pixel(px.clone(), color.pixel());
+                    self.draw(x, y, px);
+                    recolored.push((x, y));
+                }
+            }
+            self.plot.recolored.push((color, recolored));
+        }
+
+        // The lines are drawn last, so they are not recolored by the thresholds below them
+        for (row, color) in lines {
+            for x in 0..self.draw_width {
+                if self.is_blank(x, row) {
+                    let px = self.color_pixel(Pixel::Char(THRESHOLD_LINE), color.pixel());
+                    self.draw(x, row, px);
+                }
+            }
+        }
+    }
+
+    /// Mark the columns where a series had values outside of the y-axis,
+    /// with a arrow at the top or bottom of the graph
+    fn draw_overflow(&mut self) {
+        let overflow = std::mem::take(&mut self.overflow);
+        for (g, column, above) in overflow {
+            if column >= self.draw_width {
+                continue;
+            }
+            let (row, marker) = if above {
+                (0, '▲')
+            } else {
+                (self.draw_height - 1, '▼')
+            };
+            self.draw(column, row, self.series_pixel(g, Pixel::Char(marker)));
+            // The marker is written as text in a SVG, instead of the series under it
+            self.plot.cells[row * self.draw_width + column] = None;
+        }
+    }
+
+    /// Draw a graph where the area between the baseline and the values is filled.
+    /// Filled characters of earlier series are replaced, filled braille dots are added
+    /// to the dots of earlier series.
+    ///
+    /// # Arguments
+    ///
+    /// * `g` - The series to draw
+    /// * `baseline` - The level the area grows from, this must be at the bottom of a row
+    fn draw_area(&mut self, g: usize, baseline: usize) {
+        let fill = match &self.graph_type {
+            GraphType::Area(fill) => fill.clone(),
+            _ => return,
+        };
+        if fill == AreaFill::Block {
+            self.draw_bar(g, baseline);
+            return;
+        }
+        let rows = self.draw_height * self.rows_per_cell();
+        let columns = self.draw_width * self.samples_per_column();
+        for i in 0..self.y_values[g].len().min(columns) {
+            let value = self.y_values[g][i];
+            if value.is_nan() {
+                continue;
+            }
+            // Values below the baseline fill down to the value, the baseline itself is not filled
+            let level = value as usize;
+            let (bottom, top) = if level >= baseline {
+                (baseline, level)
+            } else {
+                (level, baseline - 1)
+            };
+            match fill {
+                AreaFill::Glyph(c) => {
+                    for level in bottom..=top {
+                        self.draw(i, rows - level - 1, self.series_pixel(g, Pixel::Char(c)));
+                    }
+                }
+                _ => self.draw_dot_line(
+                    g,
+                    (i, rows - bottom - 1),
+                    (i, rows - top - 1),
+                    &SubCell::Braille(BrailleType::Dot8),
+                ),
+            }
+        }
+    }
+
+    /// Draw series `g` as a sparkline on its own row, using one block for each sample
+    fn draw_spark(&mut self, g: usize) {
+        for i in 0..self.y_values[g].len().min(self.draw_width) {
+            let value = self.y_values[g][i];
+            if value.is_nan() {
+                continue;
+            }
+            let block = BAR_UP[(value as usize).min(BAR_UP.len() - 1)];
+            self.draw(i, g, self.series_pixel(g, Pixel::Char(block)));
+        }
+    }
+
+    /// Draw series `g` as a scatter plot, with a braille dot for each sample
+    ///
+    /// # Arguments
+    ///
+    /// * `g` - The series to draw
+    /// * `dots` - The dot column of each sample, see scatter_columns
+    fn draw_scatter(&mut self, g: usize, dots: &[usize]) {
+        let rows = self.draw_height * self.rows_per_cell();
+        for (i, dot) in dots.iter().enumerate() {
+            let Some(value) = self.y_values[g].get(i).cloned() else {
+                break;
+            };
+            if value.is_nan() || *dot >= self.draw_width * self.samples_per_column() {
+                continue;
+            }
+            self.draw_dot(
+                g,
+                *dot,
+                rows - value as usize - 1,
+                &SubCell::Braille(BrailleType::Dot8),
+            );
+        }
+    }
+
+    /// Draw a vertical span from the smallest to the largest value of each column of series `g`,
+    /// when it is downsampled with Downsample::MinMax.
+    /// Bars, areas and sparklines only show the largest value, so they have no spans.
+    fn draw_spans(&mut self, g: usize) {
+        let Some(lows) = self.y_lows.get(g).cloned() else {
+            return;
+        };
+        let highs = self.y_values[g].clone();
+        let spans = lows
+            .iter()
+            .zip(highs.iter())
+            .enumerate()
+            .filter(|(_, (low, high))| !low.is_nan() && !high.is_nan());
+        match &self.graph_type {
+            GraphType::Star | GraphType::Ascii => {
+                let c = if self.graph_type == GraphType::Star {
+                    SERIES_STARS[g % SERIES_STARS.len()]
+                } else {
+                    ASCII_1
+                };
+                for (i, (low, high)) in spans {
+                    let top = self.draw_height - (*high as usize) - 1;
+                    let bottom = self.draw_height - (*low as usize) - 1;
+                    for row in top..=bottom {
+                        // Keep the line that is already drawn through the column
+                        if self.is_blank(i, row) {
+                            self.draw(i, row, self.series_pixel(g, Pixel::Char(c)));
+                        }
+                    }
+                }
+            }
+            GraphType::Braille(_) | GraphType::Blocks(_) => {
+                let Some(grid) = self.graph_type.sub_cell() else {
+                    return;
+                };
+                let rows = self.draw_height * self.rows_per_cell();
+                for (i, (low, high)) in spans {
+                    let top = (i, rows - (*high as usize) - 1);
+                    let bottom = (i, rows - (*low as usize) - 1);
+                    self.draw_dot_line(g, top, bottom, &grid);
+                }
+            }
+            _ => (),
+        }
+    }
+
     /// Draw a graph using * for the pixels of the graph
     fn draw_star(&mut self, g: usize) {
         for i in 0..self.y_values[g].len() {
+            if self.y_values[g][i].is_nan() {
+                continue;
+            }
             let y = self.draw_height - (self.y_values[g][i] as usize) - 1;
             self.draw(
                 i,
                 y,
-                self.color_pixel(Pixel::Char('*'), |px| GraphPixel::Green(px)),
+                self.series_pixel(g, Pixel::Char(SERIES_STARS[g % SERIES_STARS.len()])),
             );
         }
     }
 
     /// Draw a graph using somewhat pretty ascii characters for pixels of the graph
     pub fn draw_ascii(&mut self, g: usize) {
-        if self.enable_axis {
+        let (Some(&first), Some(&last)) = (self.y_values[g].first(), self.y_values[g].last())
+        else {
+            return;
+        };
+        if self.enable_axis && !first.is_nan() {
             self.draw_exact(
                 self.col_offset - 1,
-                self.draw_height - self.y_values[g][0] as usize,
-                self.color_pixel(Pixel::Char('├'), |px| GraphPixel::Green(px)),
+                self.draw_height - first as usize,
+                self.series_pixel(g, Pixel::Char('├')),
             );
+        }
+        // The line only meets the right border if its last sample is in the last column
+        if self.enable_axis && !last.is_nan() && self.y_values[g].len() >= self.draw_width {
             self.draw_exact(
-                self.width - 1,
-                self.draw_height - self.y_values[g][self.y_values[g].len() - 1] as usize,
-                self.color_pixel(Pixel::Char('┤'), |px| GraphPixel::Green(px)),
+                self.col_offset + self.draw_width,
+                self.draw_height - last as usize,
+                self.series_pixel(g, Pixel::Char('┤')),
             );
         }
         for i in 0..self.y_values[g].len() {
+            if self.y_values[g][i].is_nan() {
+                continue;
+            }
             let y1 = self.draw_height - (self.y_values[g][i] as usize) - 1;
-            let y2 = if i < self.y_values[g].len() - 1 {
-                self.draw_height - (self.y_values[g][i + 1] as usize) - 1
-            } else {
-                y1
+            // A gap after this sample is drawn as a flat end of the line
+            let y2 = match self.y_values[g].get(i + 1) {
+                Some(y) if !y.is_nan() => self.draw_height - (*y as usize) - 1,
+                _ => y1,
             };
 
             if y1 == y2 {
-                self.draw(
-                    i,
-                    y1,
-                    self.color_pixel(Pixel::Char(ASCII_0), |px| GraphPixel::Green(px)),
-                );
+                self.draw(i, y1, self.series_pixel(g, Pixel::Char(ASCII_0)));
             } else if y1 > y2 {
-                self.draw(
-                    i,
-                    y1,
-                    self.color_pixel(Pixel::Char(ASCII_7), |px| GraphPixel::Green(px)),
-                );
-                self.draw(
-                    i,
-                    y2,
-                    self.color_pixel(Pixel::Char(ASCII_2), |px| GraphPixel::Green(px)),
-                );
+                self.draw(i, y1, self.series_pixel(g, Pixel::Char(ASCII_7)));
+                self.draw(i, y2, self.series_pixel(g, Pixel::Char(ASCII_2)));
                 for j in (y2 + 1)..y1 {
+                    self.draw(i, j, self.series_pixel(g, Pixel::Char(ASCII_1)));
+                }
+            } else {
+                self.draw(i, y1, self.series_pixel(g, Pixel::Char(ASCII_4)));
+                self.draw(i, y2, self.series_pixel(g, Pixel::Char(ASCII_3)));
+                for j in (y1 + 1)..y2 {
+                    self.draw(i, j, self.series_pixel(g, Pixel::Char(ASCII_1)));
+                }
+            }
+        }
+    }
+
+    /// Draw a graph of vertical bars using block characters, with eighths of a row of resolution.
+    /// This assumes the graph is scaled to eight levels per row.
+    ///
+    /// # Arguments
+    ///
+    /// * `g` - The series to draw
+    /// * `baseline` - The level the bars grow from, this must be at the bottom of a row
+    fn draw_bar(&mut self, g: usize, baseline: usize) {
+        let levels = self.rows_per_cell();
+        let base_row = baseline / levels;
+        for i in 0..self.y_values[g].len().min(self.draw_width) {
+            let value = self.y_values[g][i];
+            if value.is_nan() {
+                continue;
+            }
+            let level = value as usize;
+            if level >= baseline {
+                // Grow upwards from the baseline, with a partial block on top
+                let height = level - baseline;
+                for row in base_row..base_row + height / levels {
+                    self.draw(
+                        i,
+                        self.draw_height - row - 1,
+                        self.series_pixel(g, Pixel::Char(BAR_UP[levels - 1])),
+                    );
+                }
+                if !height.is_multiple_of(levels) {
+                    let row = base_row + height / levels;
                     self.draw(
                         i,
-                        j,
-                        self.color_pixel(Pixel::Char(ASCII_1), |px| GraphPixel::Green(px)),
+                        self.draw_height - row - 1,
+                        self.series_pixel(g, Pixel::Char(BAR_UP[height % levels - 1])),
                     );
                 }
             } else {
-                self.draw(
-                    i,
-                    y1,
-                    self.color_pixel(Pixel::Char(ASCII_4), |px| GraphPixel::Green(px)),
-                );
-                self.draw(
-                    i,
-                    y2,
-                    self.color_pixel(Pixel::Char(ASCII_3), |px| GraphPixel::Green(px)),
-                );
-                for j in (y1 + 1)..y2 {
+                // Hang downwards from the baseline, there are only blocks for the upper
+                // 1/8, 1/2 and 8/8 of a character, so the bottom is less precise
+                let depth = baseline - level;
+                for row in base_row - depth / levels..base_row {
                     self.draw(
                         i,
-                        j,
-                        self.color_pixel(Pixel::Char(ASCII_1), |px| GraphPixel::Green(px)),
+                        self.draw_height - row - 1,
+                        self.series_pixel(g, Pixel::Char(BAR_UP[levels - 1])),
                     );
                 }
+                let partial = match depth % levels {
+                    0 => continue,
+                    1 | 2 => '▔',
+                    3..=5 => '▀',
+                    _ => '█',
+                };
+                let row = base_row - depth / levels - 1;
+                self.draw(
+                    i,
+                    self.draw_height - row - 1,
+                    self.series_pixel(g, Pixel::Char(partial)),
+                );
+            }
+        }
+    }
+
+    /// Draw a graph using braille or block characters, with a line between each pair of samples.
+    /// Each sample is a column of dots, so there are as many samples in each column of the canvas
+    /// as there are columns of dots in a character, see SubCell.
+    /// Samples next to a gap are drawn as a single dot.
+    fn draw_dots(&mut self, g: usize) {
+        let Some(grid) = self.graph_type.sub_cell() else {
+            return;
+        };
+        let rows = self.draw_height * self.rows_per_cell();
+        let columns = self.draw_width * self.samples_per_column();
+        let dots: Vec<Option<(usize, usize)>> = self.y_values[g]
+            .iter()
+            .take(columns)
+            .enumerate()
+            .map(|(x, y)| (!y.is_nan()).then(|| (x, rows - (*y as usize) - 1)))
+            .collect();
+        for (i, dot) in dots.iter().enumerate() {
+            let Some(from) = *dot else {
+                continue;
+            };
+            match dots.get(i + 1) {
+                Some(Some(to)) => self.draw_dot_line(g, from, *to, &grid),
+                _ => self.draw_dot(g, from.0, from.1, &grid),
+            }
+        }
+    }
+
+    /// Draw a line of dots from one dot to another, in the color of series `g`
+    ///
+    /// # Arguments
+    ///
+    /// * `g` - The series the line belongs to
+    /// * `from` - Relative (x, y) position of the first dot
+    /// * `to` - Relative (x, y) position of the last dot
+    /// * `grid` - The dots inside each character
+    fn draw_dot_line(
+        &mut self,
+        g: usize,
+        from: (usize, usize),
+        to: (usize, usize),
+        grid: &SubCell,
+    ) {
+        let from = self.dot_position(from, grid);
+        let to = self.dot_position(to, grid);
+        self.canvas.dot_line(from, to, grid, self.series_color(g));
+    }
+
+    /// Whether a cell of the canvas was drawn on by a series, see Plot
+    ///
+    /// # Arguments
+    ///
+    /// * `x` - Column of the cell on the whole canvas
+    /// * `y` - Row of the cell on the whole canvas
+    fn is_plot_cell(&self, x: usize, y: usize) -> bool {
+        let (Some(x), Some(y)) = (
+            x.checked_sub(self.col_offset),
+            y.checked_sub(self.row_offset),
+        ) else {
+            return false;
+        };
+        x < self.draw_width
+            && y < self.draw_height
+            && self.plot.cells[y * self.draw_width + x].is_some()
+    }
+
+    /// The horizontal position in a SVG of the center of sample `i`
+    ///
+    /// # Arguments
+    ///
+    /// * `i` - The sample, or the dot column for scatter plots
+    fn svg_x(&self, i: usize) -> f64 {
+        let column = (i as f64 + 0.5) / self.samples_per_column() as f64;
+        (self.col_offset as f64 + column) * svg::CELL_WIDTH
+    }
+
+    /// The vertical position in a SVG of a level of series `g`, matching where
+    /// try_build draws it. For bars this is the top of the bar, for anything else
+    /// it is the center of the dot or character.
+    ///
+    /// # Arguments
+    ///
+    /// * `g` - The series, only used by sparklines
+    /// * `level` - The level, as scaled by try_build
+    fn svg_y(&self, g: usize, level: f64) -> f64 {
+        let level_height = svg::CELL_HEIGHT / self.rows_per_cell() as f64;
+        let top = self.row_offset as f64 * svg::CELL_HEIGHT;
+        let bottom = top + self.draw_height as f64 * svg::CELL_HEIGHT;
+        match self.graph_type {
+            GraphType::Bar | GraphType::Area(AreaFill::Block) => bottom - level * level_height,
+            // The lowest level of a sparkline is a block of one eighth
+            GraphType::Spark => {
+                top + (g + 1) as f64 * svg::CELL_HEIGHT - (level + 1.0) * level_height
             }
+            _ => bottom - (level + 0.5) * level_height,
         }
     }
 
-    /// Draw a graph using braille characters, this assumes the graph is scaled for 6dot characters
-    /// meaning width is divided by 2, and height is divided by 3
-    fn draw_braille(&mut self, g: usize) {
-        let mut y_scale = 1;
-        let x_scale = 2;
+    /// Draw series `g` in a SVG as lines, points, bars or areas, depending on the graph type
+    ///
+    /// # Arguments
+    ///
+    /// * `svg` - The image to draw on
+    /// * `g` - The series to draw
+    /// * `color` - The color to draw the series with
+    fn draw_svg_series(&self, svg: &mut Svg, g: usize, color: &str) {
+        let columns = self.draw_width * self.samples_per_column();
+        let levels = &self.plot.levels[g];
+        let baseline = self.plot.baselines[g] as f64;
+        // The samples that are drawn, as (x, y) positions, None for samples without a value
+        let points: Vec<Option<(f64, f64)>> = levels
+            .iter()
+            .enumerate()
+            .map(|(i, level)| {
+                match &self.graph_type {
+                    GraphType::Scatter => {
+                        self.plot.dots.get(i).cloned().filter(|dot| *dot < columns)
+                    }
+                    _ => (i < columns).then_some(i),
+                }
+                .filter(|_| !level.is_nan())
+                .map(|x| (self.svg_x(x), self.svg_y(g, *level)))
+            })
+            .collect();
+        let bar_width = 0.8 * svg::CELL_WIDTH;
 
-        let btype = self.graph_type.btype();
-        if self.graph_type == GraphType::Braille(BrailleType::Dot6) {
-            y_scale = 3;
+        match &self.graph_type {
+            GraphType::Star | GraphType::Scatter => {
+                for (x, y) in points.iter().flatten() {
+                    svg.circle(*x, *y, 2.5, color);
+                }
+            }
+            GraphType::Bar | GraphType::Area(AreaFill::Block) | GraphType::Spark => {
+                let base = if self.graph_type == GraphType::Spark {
+                    self.svg_y(g, -1.0)
+                } else {
+                    self.svg_y(g, baseline)
+                };
+                // Bars less than half a level high are not drawn in the text graph either
+                let min_height = svg::CELL_HEIGHT / self.rows_per_cell() as f64 / 2.0;
+                for (x, y) in points.iter().flatten() {
+                    if (y - base).abs() < min_height {
+                        continue;
+                    }
+                    svg.rect(
+                        x - bar_width / 2.0,
+                        y.min(base),
+                        bar_width,
+                        (y - base).abs(),
+                        color,
+                    );
+                }
+            }
+            GraphType::Area(_) => {
+                // The baseline level itself is filled, so the area starts at its bottom edge
+                let base = self.svg_y(g, baseline - 0.5);
+                for segment in points.split(|point| point.is_none()) {
+                    let line: Vec<(f64, f64)> = segment.iter().flatten().cloned().collect();
+                    let (Some(first), Some(last)) = (line.first(), line.last()) else {
+                        continue;
+                    };
+                    let mut shape = vec![(first.0, base)];
+                    shape.extend(line.iter().cloned());
+                    shape.push((last.0, base));
+                    svg.polygon(&shape, color, 0.35);
+                    svg.polyline(&line, color);
+                }
+            }
+            GraphType::Ascii | GraphType::Braille(_) | GraphType::Blocks(_) => {
+                for segment in points.split(|point| point.is_none()) {
+                    let line: Vec<(f64, f64)> = segment.iter().flatten().cloned().collect();
+                    match line.as_slice() {
+                        [(x, y)] => svg.circle(*x, *y, 1.5, color),
+                        _ => svg.polyline(&line, color),
+                    }
+                }
+            }
         }
-        if self.graph_type == GraphType::Braille(BrailleType::Dot8) {
-            y_scale = 4;
+
+        // The span of each column of Downsample::MinMax, bars and areas only show the largest value
+        if let (Some(lows), false) = (
+            self.plot.lows.get(g),
+            matches!(
+                self.graph_type,
+                GraphType::Bar | GraphType::Area(_) | GraphType::Spark
+            ),
+        ) {
+            for (i, (low, high)) in lows.iter().zip(levels.iter()).enumerate().take(columns) {
+                if !low.is_nan() && !high.is_nan() {
+                    let x = self.svg_x(i);
+                    svg.line((x, self.svg_y(g, *low)), (x, self.svg_y(g, *high)), color);
+                }
+            }
         }
+    }
+}
+
+impl std::fmt::Display for GraphBuilder {
+    /// Turn canvas into a string
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{}", self.canvas)
+    }
+}
 
-        let mut i = 0;
-        while i < self.y_values[g].len() - 1 {
-            let y1 = (self.draw_height * y_scale) - (self.y_values[g][i] as usize) - 1;
-            let y1_abs = y1 / y_scale;
-            let y2 = (self.draw_height * y_scale) - (self.y_values[g][i + 1] as usize) - 1;
-            let y2_abs = y2 / y_scale;
+/// Make a range of the y-axis that values can be drawn in, if it is empty or a single value.
+/// An empty range means there is nothing to draw, the range is then put around the bound
+/// that is set, if any. A single value is put in the middle of the range.
+///
+/// # Arguments
+///
+/// * `min_y` - The bottom of the range
+/// * `max_y` - The top of the range
+/// * `y_min` - The fixed lower bound, if any
+/// * `y_max` - The fixed upper bound, if any
+fn padded_range(
+    mut min_y: f64,
+    mut max_y: f64,
+    y_min: Option<f64>,
+    y_max: Option<f64>,
+) -> (f64, f64) {
+    if min_y > max_y {
+        (min_y, max_y) = match (y_min, y_max) {
+            (Some(y), None) => (y, y),
+            (None, Some(y)) => (y, y),
+            _ => (0.0, 0.0),
+        };
+    }
+    if min_y == max_y {
+        let padding = if min_y == 0.0 { 1.0 } else { min_y.abs() / 2.0 };
+        min_y = (min_y - padding).max(f64::MIN);
+        max_y = (max_y + padding).min(f64::MAX);
+    }
+    (min_y, max_y)
+}
 
-            let pxx1 = brr(1 << y1 % y_scale, &btype);
-            let pxx2 = brr(1 << (y2 % y_scale) + y_scale, &btype);
-            if y1_abs == y2_abs {
-                self.draw(
-                    i / x_scale,
-                    y1_abs,
-                    self.color_pixel(Pixel::Braille(pxx1 | pxx2), |px| GraphPixel::Green(px)),
-                );
-            } else {
-                self.draw(
-                    i / x_scale,
-                    y1_abs,
-                    self.color_pixel(Pixel::Braille(pxx1), |px| GraphPixel::Green(px)),
-                );
-                self.draw(
-                    i / x_scale,
-                    y2_abs,
-                    self.color_pixel(Pixel::Braille(pxx2), |px| GraphPixel::Green(px)),
-                );
+/// The level of `value` on a y-axis from `min_y` to `max_y`, with `levels` levels.
+///
+/// # Arguments
+///
+/// * `value` - The value to find the level of
+/// * `range` - The bottom and top of the y-axis
+/// * `levels` - The number of levels of the y-axis, see scale_levels
+fn scale_level(value: f64, (min_y, max_y): (f64, f64), levels: usize) -> f64 {
+    half_span(min_y, value) * ((levels - 1) as f64 / half_span(min_y, max_y))
+}
+
+/// Check whether the x values are strictly increasing with a constant distance between them,
+/// in which case the samples can be downsampled without looking at the x values.
+///
+/// # Arguments
+///
+/// * `x_values` - The x values to check
+fn evenly_spaced(x_values: &[f64]) -> bool {
+    if x_values.len() < 3 {
+        return x_values.windows(2).all(|w| w[1] > w[0]);
+    }
+    let step = x_values[1] - x_values[0];
+    let tolerance = step.abs() * 1e-6;
+    step > 0.0
+        && x_values
+            .windows(2)
+            .all(|w| ((w[1] - w[0]) - step).abs() <= tolerance)
+}
+
+/// Scale x values to the columns of a scatter plot, the smallest x value ends up in the first
+/// column, and the largest in the last. Values that are not finite are put in the first column.
+///
+/// # Arguments
+///
+/// * `x_values` - The x values of the samples
+/// * `column_count` - Desired resolution of the output
+fn scatter_columns(x_values: &[f64], column_count: usize) -> Vec<usize> {
+    let finite = x_values.iter().cloned().filter(|x| x.is_finite());
+    let min_x = finite.clone().fold(f64::INFINITY, f64::min);
+    let max_x = finite.fold(f64::NEG_INFINITY, f64::max);
+    x_values
+        .iter()
+        .map(|x| {
+            if !x.is_finite() || max_x <= min_x {
+                return 0;
+            }
+            (fraction(*x, min_x, max_x) * (column_count.max(1) - 1) as f64).round() as usize
+        })
+        .collect()
+}
+
+/// The evenly spaced x values that `interpolate` resamples onto
+///
+/// # Arguments
+///
+/// * `x_values` - The x values of the samples
+/// * `column_count` - Desired resolution of the output
+fn column_grid(x_values: &[f64], column_count: usize) -> Vec<f64> {
+    let finite = x_values.iter().cloned().filter(|x| x.is_finite());
+    let min_x = finite.clone().fold(f64::INFINITY, f64::min);
+    let max_x = finite.fold(f64::NEG_INFINITY, f64::max);
+    if column_count < 2 || min_x > max_x {
+        return vec![min_x; column_count];
+    }
+    (0..column_count)
+        .map(|i| match i {
+            _ if i == column_count - 1 => max_x,
+            _ => lerp(min_x, max_x, i as f64 / (column_count - 1) as f64),
+        })
+        .collect()
+}
+
+/// Resample a series with unevenly spaced x values onto `column_count` evenly spaced x values.
+/// If there are more samples than columns, the samples closest to each column are combined
+/// with `downsample` first, and the columns without samples are interpolated from the columns
+/// around them. Otherwise every column is interpolated from the samples, see interpolate.
+/// Returns the value of each column, and the smallest value of each column,
+/// which is only different from the value for Downsample::MinMax.
+///
+/// # Arguments
+///
+/// * `y_values` - The y values that should be resampled
+/// * `x_values` - The x value of each sample
+/// * `column_count` - Desired resolution of the output
+/// * `max_gap` - Largest distance between two samples that will be interpolated across
+/// * `downsample` - How samples in the same column are combined
+fn resample(
+    y_values: &[f64],
+    x_values: &[f64],
+    column_count: usize,
+    max_gap: Option<f64>,
+    downsample: &Downsample,
+) -> (Vec<f64>, Vec<f64>) {
+    if *downsample == Downsample::Nearest || y_values.len() <= column_count {
+        let values = interpolate(y_values, x_values, column_count, max_gap);
+        return (values.clone(), values);
+    }
+    let grid = column_grid(x_values, column_count);
+    let (x_values, (lows, highs)): (Vec<f64>, (Vec<f64>, Vec<f64>)) = grid
+        .into_iter()
+        .zip(downsample::uneven(
+            y_values,
+            x_values,
+            column_count,
+            downsample,
+        ))
+        .filter_map(|(x, column)| column.map(|span| (x, span)))
+        .unzip();
+    (
+        interpolate(&highs, &x_values, column_count, max_gap),
+        interpolate(&lows, &x_values, column_count, max_gap),
+    )
+}
+
+/// A better way to downsize, heavier and more complex, but should be used when sample speed is uneven.
+/// The samples are resampled onto `column_count` evenly spaced x values by linear interpolation.
+/// Samples do not have to be sorted by x, samples with the same x value are averaged,
+/// and samples without a finite x value are ignored.
+/// Columns that fall in a gap between two samples larger than `max_gap` are NaN, as are columns
+/// next to a sample without a finite y value, so such samples are gaps in the line.
+///
+/// # Arguments
+///
+/// * `y_values` - The y values that should be downsampled
+/// * `x_values` - X values, needed to interpolate while keeping sample distance
+/// * `column_count` - Desired resolution of the output
+/// * `max_gap` - Largest distance between two samples that will be interpolated across
+pub fn interpolate(
+    y_values: &[f64],
+    x_values: &[f64],
+    column_count: usize,
+    max_gap: Option<f64>,
+) -> Vec<f64> {
+    let mut samples: Vec<(f64, f64)> = x_values
+        .iter()
+        .cloned()
+        .zip(y_values.iter().cloned())
+        .filter(|(x, _)| x.is_finite())
+        .collect();
+    samples.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+    // Merge samples with duplicate x values into the mean of their finite y values
+    let mut merged: Vec<(f64, f64, usize)> = Vec::with_capacity(samples.len());
+    for (x, y) in samples {
+        let (y, count) = if y.is_finite() { (y, 1) } else { (0.0, 0) };
+        match merged.last_mut() {
+            Some((last_x, sum, last_count)) if *last_x == x => {
+                *sum += y;
+                *last_count += count;
             }
-            i += 2;
+            _ => merged.push((x, y, count)),
+        }
+    }
+    let samples: Vec<(f64, f64)> = merged
+        .into_iter()
+        .map(|(x, sum, count)| match count {
+            0 => (x, f64::NAN),
+            _ => (x, sum / count as f64),
+        })
+        .collect();
+
+    if samples.is_empty() || column_count == 0 {
+        return vec![f64::NAN; column_count];
+    }
+    if samples.len() == 1 || column_count == 1 {
+        return vec![samples[samples.len() - 1].1; column_count];
+    }
+
+    let sample_x: Vec<f64> = samples.iter().map(|(x, _)| *x).collect();
+    let mut interpolated_data = Vec::with_capacity(column_count);
+
+    let mut j = 0;
+    for target_mark in column_grid(&sample_x, column_count) {
+        while j < samples.len() - 2 && samples[j + 1].0 < target_mark {
+            j += 1;
         }
+        let (t0, d0) = samples[j];
+        let (t1, d1) = samples[j + 1];
+        // A column on a sample gets its value, even if the sample is next to a gap,
+        // otherwise a sample without a value makes the column NaN
+        let value = if target_mark == t1 {
+            d1
+        } else if target_mark == t0 {
+            d0
+        } else if max_gap.is_some_and(|max_gap| t1 - t0 > max_gap) {
+            f64::NAN
+        } else {
+            lerp(d0, d1, fraction(target_mark, t0, t1))
+        };
+        interpolated_data.push(value);
     }
+
+    interpolated_data
 }
 
-// /// A better way to downsize, heavier and more complex, but should be used when sample speed is uneven.
-// ///
-// /// # Arguments
-// ///
-// /// * `y_values` - The y values that should be downsampled
-// /// * `x_values` - X values, needed to interpolate while keeping sample distance
-// /// * `column_count` - Desired resolution of the output
-// pub fn interpolate(y_values: &[f64], x_values: &[f64], column_count: usize) -> Vec<f64> {
-//     let min_x = x_values.iter().cloned().fold(f64::INFINITY, f64::min);
-//     let max_x = x_values.iter().cloned().fold(f64::NEG_INFINITY, f64::max);
-//     let step = (max_x - min_x) / (column_count as f64 - 1.0);
-//     let mut interpolated_data = Vec::new();
-//
-//     for i in 0..column_count {
-//         let target_mark = min_x + i as f64 * step;
-//         let mut j = 0;
-//         while j < x_values.len() - 1 && x_values[j + 1] < target_mark {
-//             j += 1;
-//         }
-//         let t0 = x_values[j];
-//         let t1 = x_values[j + 1];
-//         let d0 = y_values[j];
-//         let d1 = y_values[j + 1];
-//         let value = d0 + (d1 - d0) * (target_mark - t0) / (t1 - t0);
-//         interpolated_data.push(value);
-//     }
-//
-//     interpolated_data
-// }
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::same;
+
+    /// A graph of `values` against their index, drawn without colors
+    ///
+    /// # Arguments
+    ///
+    /// * `values` - The values of the graph
+    /// * `width` - Width of the canvas
+    /// * `height` - Height of the canvas
+    fn graph(values: &[f64], width: usize, height: usize) -> GraphBuilder {
+        let x_values: Vec<f64> = (0..values.len()).map(|x| x as f64).collect();
+        let mut graph = GraphBuilder::new(&x_values, values, width, height);
+        graph.color(false);
+        graph
+    }
+
+    #[test]
+    fn interpolate_between_samples() {
+        assert_eq!(
+            interpolate(&[0.0, 10.0], &[0.0, 10.0], 3, None),
+            vec![0.0, 5.0, 10.0]
+        );
+        assert_eq!(
+            interpolate(&[0.0, 4.0, 2.0], &[0.0, 1.0, 3.0], 4, None),
+            vec![0.0, 4.0, 3.0, 2.0]
+        );
+    }
+
+    #[test]
+    fn interpolate_sorts_and_merges_samples() {
+        let y_values = [4.0, 0.0, 6.0, 9.0];
+        let x_values = [2.0, 0.0, 2.0, f64::NAN];
+        assert_eq!(
+            interpolate(&y_values, &x_values, 3, None),
+            vec![0.0, 2.5, 5.0]
+        );
+    }
+
+    #[test]
+    fn interpolate_leaves_gaps_at_nan() {
+        let y_values = [0.0, 1.0, f64::NAN, 3.0, 4.0];
+        let x_values = [0.0, 1.0, 2.0, 3.0, 4.0];
+        assert!(same(
+            &interpolate(&y_values, &x_values, 5, None),
+            &[0.0, 1.0, f64::NAN, 3.0, 4.0]
+        ));
+        assert!(same(
+            &interpolate(&y_values, &x_values, 9, None),
+            &[0.0, 0.5, 1.0, f64::NAN, f64::NAN, f64::NAN, 3.0, 3.5, 4.0]
+        ));
+    }
+
+    #[test]
+    fn interpolate_leaves_gaps_wider_than_max_gap() {
+        let values = [0.0, 1.0, 5.0];
+        assert!(same(
+            &interpolate(&values, &values, 6, Some(2.0)),
+            &[0.0, 1.0, f64::NAN, f64::NAN, f64::NAN, 5.0]
+        ));
+    }
+
+    #[test]
+    fn uneven_x_values_use_a_column_for_each_sample() {
+        let y_values = [1.0, 3.0, 2.0, 5.0, 4.0];
+        let mut even = GraphBuilder::new(&[0.0, 1.0, 2.0, 3.0, 4.0], &y_values, 20, 4);
+        let mut uneven = GraphBuilder::new(&[0.0, 1.0, 2.0, 3.1, 4.0], &y_values, 20, 4);
+        even.color(false);
+        uneven.color(false);
+        let graph = uneven.build();
+        assert_eq!(graph, even.build());
+        assert!(graph.lines().all(|line| line[5..].trim().is_empty()));
+    }
+
+    #[test]
+    fn build_twice_gives_the_same_graph() {
+        let values: Vec<f64> = (0..40).map(|x| x as f64).collect();
+        let mut graph = graph(&values, 30, 8);
+        graph.axis(true);
+        graph.cut_overflow(true);
+        let first = graph.build();
+        assert_eq!(graph.build(), first);
+        assert_eq!(graph.y_range(), (10.0, 39.0));
+    }
+
+    #[test]
+    fn stats_of_the_drawn_samples() {
+        let values: Vec<f64> = (0..100).map(|x| x as f64).collect();
+        let mut graph = graph(&values, 30, 8);
+        graph.build();
+        assert!(graph.stats().is_empty());
+
+        graph.footer(true);
+        graph.cut_overflow(true);
+        graph.build();
+        let stats = &graph.stats()[0];
+        assert_eq!(stats.count, 30);
+        assert_eq!((stats.min, stats.max, stats.last), (70.0, 99.0, 99.0));
+    }
+
+    #[test]
+    fn spark_values_leave_half_of_the_width() {
+        let values: Vec<f64> = (0..100).map(|x| x as f64).collect();
+        let mut graph = graph(&values, 40, 1);
+        graph.graph_type(GraphType::Spark);
+        graph.spark_values(true);
+        let line = graph.build();
+        let (spark, values) = line.trim_end().split_at(line.find(' ').unwrap());
+        assert_eq!(spark.chars().count(), 20);
+        assert_eq!(values, " min 0 max 99");
+    }
+
+    #[test]
+    fn min_max_downsampling_draws_the_envelope() {
+        let values: Vec<f64> = (0..40)
+            .map(|i| if i % 2 == 0 { 10.0 } else { 0.0 })
+            .collect();
+        let mut graph = graph(&values, 10, 4);
+        assert_eq!(graph.build().lines().next(), Some("**********"));
+        graph.downsample(Downsample::MinMax);
+        assert!(graph.build().lines().all(|line| line == "**********"));
+    }
+
+    #[test]
+    fn y_axis_labels_are_nice_and_si_formatted() {
+        let values: Vec<f64> = (0..=20).map(|i| i as f64 * 100.0).collect();
+        let mut graph = graph(&values, 30, 7);
+        graph.axis(true);
+        let labels: Vec<String> = graph
+            .build()
+            .lines()
+            .map(|line| line.chars().take(7).collect::<String>().trim().to_string())
+            .collect();
+        assert_eq!(labels, ["", "2k", "1k", "", "0", "", ""]);
+    }
+
+    #[test]
+    fn histogram_labels_the_outer_bin_edges() {
+        let values = [1.0, 2.0, 2.0, 3.0, 3.0, 3.0, 4.0, 4.0, 5.0];
+        let mut graph = GraphBuilder::histogram(&values, Binning::Auto, 30, 7);
+        graph.axis(true);
+        let graph = graph.build();
+        let labels: Vec<&str> = graph.lines().last().unwrap().split_whitespace().collect();
+        assert_eq!(labels.first(), Some(&"1"));
+        assert_eq!(labels.last(), Some(&"6"));
+    }
+
+    #[test]
+    fn footer_names_each_series() {
+        let mut graph = graph(&[1.0, 2.0, 3.0], 40, 8);
+        graph.name("a");
+        graph.add_series("b", &[4.0, 5.0, 6.0]);
+        graph.footer(true);
+        let graph = graph.build();
+        let footer: Vec<&str> = graph.lines().skip(6).map(|line| line.trim_end()).collect();
+        assert_eq!(
+            footer,
+            [
+                "a: n 3 min 1 max 3 mean 2 sd 816.5m",
+                "b: n 3 min 4 max 6 mean 5 sd 816.5m"
+            ]
+        );
+    }
+
+    #[test]
+    fn every_series_is_drawn() {
+        let mut graph = graph(&[0.0, 1.0, 2.0, 3.0], 4, 4);
+        graph.add_series("b", &[3.0, 2.0, 1.0, 0.0]);
+        assert_eq!(graph.build(), "+  *\n +* \n *+ \n*  +");
+    }
+
+    #[test]
+    fn x_axis_labels_are_the_x_values() {
+        let x_values: Vec<f64> = (0..=10).map(|i| 100.0 + i as f64 * 10.0).collect();
+        let y_values: Vec<f64> = (0..=10).map(|i| i as f64).collect();
+        let mut graph = GraphBuilder::new(&x_values, &y_values, 30, 5);
+        graph.axis(true);
+        let graph = graph.build();
+        let labels: Vec<&str> = graph.lines().last().unwrap().split_whitespace().collect();
+        assert_eq!(labels, ["100", "150", "200"]);
+    }
+
+    #[test]
+    fn y_bounds_fix_the_range() {
+        let mut both = graph(&[1.0, 2.0, 3.0], 10, 5);
+        both.y_bounds(Some(-5.0), Some(5.0));
+        both.build();
+        assert_eq!(both.y_range(), (-5.0, 5.0));
+
+        let mut lower = graph(&[1.0, 2.0, 3.0], 10, 5);
+        lower.y_bounds(Some(0.0), None);
+        lower.build();
+        assert_eq!(lower.y_range(), (0.0, 3.0));
+    }
+
+    #[test]
+    fn include_zero_anchors_the_range() {
+        let mut positive = graph(&[2.0, 3.0], 10, 5);
+        positive.include_zero(true);
+        positive.build();
+        assert_eq!(positive.y_range(), (0.0, 3.0));
+
+        let mut negative = graph(&[-3.0, -2.0], 10, 5);
+        negative.include_zero(true);
+        negative.build();
+        assert_eq!(negative.y_range(), (-3.0, 0.0));
+    }
+
+    #[test]
+    fn sticky_range_grows_at_once_and_shrinks_slowly() {
+        let mut graph = graph(&[2.0, 4.0], 10, 5);
+        graph.sticky_range((0.0, 3.0), 0.5);
+        graph.build();
+        assert_eq!(graph.y_range(), (1.0, 4.0));
+    }
+
+    #[test]
+    fn last_sample_is_marked_only_in_the_last_column() {
+        let mut full = graph(&[1.0, 2.0, 3.0, 4.0], 4, 4);
+        full.graph_type(GraphType::Ascii);
+        full.axis(true);
+        assert!(full.build().contains('┤'));
+
+        let mut short = graph(&[1.0, 2.0], 20, 4);
+        short.graph_type(GraphType::Ascii);
+        short.axis(true);
+        short.cut_overflow(true);
+        assert!(!short.build().contains('┤'));
+    }
+
+    #[test]
+    fn log_scale_inverts_its_values() {
+        assert!((YScale::Log10.apply(1000.0) - 3.0).abs() < 1e-12);
+        assert_eq!(YScale::Log2.invert(3.0), 8.0);
+        assert!(YScale::Log10.apply(0.0).is_nan());
+        assert!(YScale::Log2.apply(-1.0).is_nan());
+        assert_eq!(YScale::Linear.apply(-1.0), -1.0);
+    }
+
+    #[test]
+    fn log_scale_labels_powers_of_ten() {
+        let mut graph = graph(&[1.0, 10.0, 100.0, 1000.0], 30, 8);
+        graph.y_scale(YScale::Log10);
+        graph.axis(true);
+        let labels: Vec<String> = graph
+            .build()
+            .lines()
+            .map(|line| line.chars().take(7).collect::<String>().trim().to_string())
+            .collect();
+        assert_eq!(labels, ["", "1k", "100", "", "10", "1", "", ""]);
+    }
+
+    #[test]
+    fn bars_rise_in_eighths_of_a_cell() {
+        let values: Vec<f64> = (0..=16).map(|i| i as f64).collect();
+        let mut graph = graph(&values, 17, 2);
+        graph.graph_type(GraphType::Bar);
+        assert_eq!(graph.build(), "         ▁▂▃▄▅▆▇█\n ▁▂▃▄▅▆▇█████████");
+    }
+
+    #[test]
+    fn negative_bars_hang_below_zero() {
+        let mut graph = graph(&[-2.0, -1.0, 1.0, 2.0], 4, 2);
+        graph.graph_type(GraphType::Bar);
+        assert_eq!(graph.build(), "  ▄█\n█▀  ");
+    }
+
+    #[test]
+    fn spark_has_one_row_for_each_series() {
+        let values: Vec<f64> = (1..=8).map(|i| i as f64).collect();
+        let mut graph = graph(&values, 8, 3);
+        graph.graph_type(GraphType::Spark);
+        assert_eq!(graph.build(), "▁▂▃▄▅▆▇█");
+        graph.add_series("b", &[8.0, 7.0, 6.0, 5.0, 4.0, 3.0, 2.0, 1.0]);
+        assert_eq!(graph.build(), "▁▂▃▄▅▆▇█\n█▇▆▅▄▃▂▁");
+    }
+
+    #[test]
+    fn scatter_columns_span_the_x_values() {
+        assert_eq!(scatter_columns(&[0.0, 10.0, 1.0, 5.0], 8), vec![0, 7, 1, 4]);
+        assert_eq!(scatter_columns(&[3.0, 3.0], 8), vec![0, 0]);
+        assert_eq!(scatter_columns(&[f64::NAN, 0.0, 2.0], 3), vec![0, 0, 2]);
+    }
+
+    #[test]
+    fn scatter_plots_points_in_any_order() {
+        let mut graph = GraphBuilder::new(&[0.0, 10.0, 1.0], &[0.0, 1.0, 1.0], 4, 2);
+        graph.color(false);
+        graph.graph_type(GraphType::Scatter);
+        assert_eq!(graph.build(), "⠈  ⠈\n⡀   ");
+    }
+
+    #[test]
+    fn braille_joins_neighbouring_samples() {
+        let mut rise = graph(&[0.0, 7.0], 2, 2);
+        rise.graph_type(GraphType::Braille(BrailleType::Dot8));
+        assert_eq!(rise.build(), "⢸ \n⡇ ");
+
+        let mut peak = graph(&[0.0, 7.0, 0.0], 2, 2);
+        peak.graph_type(GraphType::Braille(BrailleType::Dot8));
+        assert_eq!(peak.build(), "⢸ \n⡇⡇");
+    }
+
+    #[test]
+    fn area_is_filled_from_the_bottom() {
+        let mut block = graph(&[0.0, 1.0, 2.0, 3.0], 4, 3);
+        block.graph_type(GraphType::Area(AreaFill::Block));
+        assert_eq!(block.build(), "   █\n  ██\n ███");
+
+        let mut glyph = graph(&[0.0, 1.0, 2.0, 3.0], 4, 3);
+        glyph.graph_type(GraphType::Area(AreaFill::Glyph('#')));
+        assert_eq!(glyph.build(), "   #\n ###\n####");
+    }
+
+    #[test]
+    fn later_areas_are_drawn_on_top() {
+        let mut graph = graph(&[1.0, 1.0, 1.0, 1.0], 4, 4);
+        graph.graph_type(GraphType::Area(AreaFill::Glyph('#')));
+        graph.add_series("b", &[2.0, 0.0, 2.0, 0.0]);
+        graph.y_bounds(Some(0.0), Some(2.0));
+        graph.color(true);
+        let graph = graph.build();
+        let first = "\u{1b}[32m#\u{1b}[0m";
+        let second = "\u{1b}[34m#\u{1b}[0m";
+        let rows: Vec<&str> = graph.lines().collect();
+        assert_eq!(rows[0], format!("{second} {second} "));
+        assert_eq!(rows[1], format!("{second}{first}{second}{first}"));
+    }
+
+    #[test]
+    fn blocks_have_two_rows_in_each_character() {
+        let mut half = graph(&[0.0, 3.0], 2, 2);
+        half.graph_type(GraphType::Blocks(BlockType::Half));
+        assert_eq!(half.build(), " █\n█ ");
+
+        // Quadrants also have two columns, so both samples share the first character
+        let mut quadrant = graph(&[0.0, 3.0], 2, 2);
+        quadrant.graph_type(GraphType::Blocks(BlockType::Quadrant));
+        assert_eq!(quadrant.build(), "▐ \n▌ ");
+    }
+
+    #[test]
+    fn threshold_line_and_what_is_above_it() {
+        let mut graph = graph(&[0.0, 1.0, 2.0, 3.0], 4, 4);
+        graph.threshold(1.5, Color::Red);
+        assert_eq!(graph.build(), "   *\n┄┄*┄\n *  \n*   ");
+
+        graph.color(true);
+        let graph = graph.build();
+        let rows: Vec<&str> = graph.lines().collect();
+        assert_eq!(rows[0], "   \u{1b}[31m*\u{1b}[0m");
+        assert_eq!(rows[2], " \u{1b}[32m*\u{1b}[0m  ");
+    }
+
+    #[test]
+    fn y_range_includes_the_thresholds() {
+        let mut graph = graph(&[0.0, 1.0, 2.0, 3.0], 4, 4);
+        graph.threshold(10.0, Color::Red);
+        graph.build();
+        assert_eq!(graph.y_range(), (0.0, 10.0));
+    }
+
+    #[test]
+    fn annotations_label_the_min_max_and_last_value() {
+        let mut graph = graph(&[5.0, 1.0, 9.0, 3.0, 4.0, 6.0, 2.0, 7.0], 16, 8);
+        graph.annotate(true);
+        let graph = graph.build();
+        let rows: Vec<&str> = graph.lines().map(|line| line.trim_end()).collect();
+        assert_eq!(rows[0], "  * max 9");
+        assert_eq!(rows[2], "last 7 *");
+        assert_eq!(rows[7], " * min 1");
+    }
+
+    #[test]
+    fn edge_value_is_written_right_of_the_axis() {
+        let values = [5.0, 1.0, 9.0, 3.0, 4.0, 6.0, 2.0, 7.0];
+        let mut graph = graph(&values, 30, 8);
+        graph.axis(true);
+        assert!(!graph.build().lines().any(|line| line.ends_with("│7")));
+        graph.edge_value(true);
+        let graph = graph.build();
+        let rows: Vec<&str> = graph.lines().map(|line| line.trim_end()).collect();
+        assert_eq!(rows[2], "      6│     * *      │7");
+    }
+
+    #[test]
+    fn try_build_explains_why_there_is_no_graph() {
+        assert_eq!(
+            graph(&[1.0, 2.0], 0, 3).try_build(),
+            Err(GraphError::EmptyCanvas)
+        );
+        let mut graph = graph(&[1.0, 2.0], 3, 3);
+        graph.y_bounds(Some(2.0), Some(1.0));
+        assert_eq!(
+            graph.try_build(),
+            Err(GraphError::InvalidRange {
+                y_min: 2.0,
+                y_max: 1.0
+            })
+        );
+        assert_eq!(graph.build(), "");
+        assert_eq!(
+            graph.try_build().unwrap_err().to_string(),
+            "the y-axis minimum 2 is above the maximum 1"
+        );
+    }
+
+    #[test]
+    fn flat_empty_and_non_finite_data_are_drawn() {
+        assert_eq!(graph(&[2.0, 2.0, 2.0], 3, 3).build(), "   \n***\n   ");
+        assert_eq!(
+            graph(&[], 3, 3).try_build(),
+            Ok("   \n   \n   ".to_string())
+        );
+        let values = [f64::NAN, 1.0, f64::INFINITY, 3.0];
+        assert_eq!(graph(&values, 4, 3).build(), "   *\n    \n *  ");
+    }
+
+    #[test]
+    fn small_canvases_drop_labels_then_axis_then_rows() {
+        let values: Vec<f64> = (0..50).map(|i| (i as f64 * 1000.0).sin() * 1e6).collect();
+        let layout = |width, height| {
+            let mut graph = graph(&values, width, height);
+            graph.axis(true);
+            graph.build()
+        };
+        assert_eq!(layout(8, 3), "┌──────┐\n│******│\n└──────┘");
+        assert_eq!(layout(3, 2), " **\n*  ");
+        assert_eq!(layout(1, 1), "▄");
+    }
+
+    #[test]
+    fn every_canvas_size_fits_the_graph() {
+        let values: Vec<f64> = (0..50).map(|i| (i as f64 * 1000.0).sin() * 1e6).collect();
+        for width in 1..24 {
+            for height in 1..10 {
+                let mut graph = graph(&values, width, height);
+                graph.axis(true);
+                graph.add_series("b", &values);
+                graph.title("title");
+                graph.legend(true);
+                let graph = graph.build();
+                assert!(graph.lines().count() <= height);
+                assert!(graph.lines().all(|line| display_width(line) == width));
+            }
+        }
+    }
+
+    #[test]
+    fn title_labels_and_legend_frame_the_graph() {
+        let mut graph = graph(&[1.0, 2.0, 3.0, 4.0], 30, 9);
+        graph.axis(true);
+        graph.name("load");
+        graph.add_series("disk", &[4.0, 3.0, 2.0, 1.0]);
+        graph.title("Title");
+        graph.x_label("time");
+        graph.y_label("value");
+        graph.legend(true);
+        let graph = graph.build();
+        let rows: Vec<&str> = graph.lines().collect();
+        assert_eq!(rows[0].trim(), "Title");
+        assert_eq!(rows[8].trim(), "time");
+        // The y-axis label is written along the rows of the graph, what does not fit is left out
+        let y_label: String = rows[2..7]
+            .iter()
+            .filter_map(|row| row.chars().next())
+            .collect();
+        assert_eq!(y_label, "valu ");
+        assert!(rows[2].ends_with("│* load│"));
+        assert!(rows[3].ends_with("│+ disk│"));
+    }
+
+    #[test]
+    fn gridlines_at_the_ticks_behind_the_graph() {
+        let values: Vec<f64> = (0..=20).map(|i| i as f64 * 100.0).collect();
+        let mut graph = graph(&values, 30, 7);
+        graph.axis(true);
+        graph.grid(true, true);
+        assert_eq!(
+            graph.build(),
+            [
+                "       ┌─────┬────┬────┬─────┐",
+                "     2k│     │    │    │ ****│",
+                "     1k┼─────┼────*******────┤",
+                "       │    ******│    │     │",
+                "      0│**** │    │    │     │",
+                "       └─────┼────┼────┼─────┘",
+                "        0    5   10   15   20 ",
+            ]
+            .join("\n")
+        );
+    }
+
+    #[test]
+    fn right_axis_has_its_own_range_and_labels() {
+        let mut graph = graph(&[1.0, 2.0, 3.0, 4.0], 30, 7);
+        graph.axis(true);
+        graph.add_series("big", &[1000.0, 3000.0, 2000.0, 4000.0]);
+        graph.series_axis(1, YAxis::Right);
+        graph.right_y_label("big");
+        assert_eq!(
+            graph.build(),
+            [
+                "       ┌────────────┐         ",
+                "      4│   +        │4k      b",
+                "      3│ +*         │3k      i",
+                "      2│ *+         │2k      g",
+                "      1│+           │1k       ",
+                "       └────────────┘         ",
+                "        0                     ",
+            ]
+            .join("\n")
+        );
+        assert_eq!(graph.y_range(), (1.0, 4.0));
+        assert_eq!(graph.right_y_range(), (1000.0, 4000.0));
+    }
+
+    #[test]
+    fn svg_draws_samples_as_circles_and_lines() {
+        let stars = graph(&[0.0, 1.0], 2, 2).build_svg();
+        assert!(stars.starts_with("<svg "));
+        assert!(stars.contains(r#"<circle cx="5.0" cy="30.0" r="2.5" fill="black"/>"#));
+        assert!(stars.contains(r#"<circle cx="15.0" cy="10.0" r="2.5" fill="black"/>"#));
+
+        let mut ascii = graph(&[0.0, 1.0, 0.0], 3, 2);
+        ascii.graph_type(GraphType::Ascii);
+        assert!(ascii
+            .build_svg()
+            .contains(r#"<polyline points="5.0,30.0 15.0,10.0 25.0,30.0""#));
+    }
+}
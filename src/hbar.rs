@@ -0,0 +1,376 @@
+use crate::canvas::{Canvas, GraphPixel, Pixel};
+use crate::graph::{Color, SERIES_COLORS};
+use crate::svg::{self, Svg};
+use crate::text::{display_width, truncate};
+use crate::ticks::format_si;
+
+/// Blocks filling the left 1/8 to 8/8 of a character, used for bars growing to the right
+const BAR_RIGHT: [char; 8] = ['▏', '▎', '▍', '▌', '▋', '▊', '▉', '█'];
+
+/// Available orders for the bars of a horizontal bar chart
+#[derive(PartialEq, Clone, Default)]
+pub enum HBarSort {
+    /// Keep the order of the input
+    #[default]
+    Input,
+    /// Smallest value first
+    Ascending,
+    /// Largest value first
+    Descending,
+    /// Alphabetically by label
+    Label,
+}
+
+/// Temporary variables used while building a horizontal bar chart,
+/// which has one labelled bar per row, instead of a series of samples
+pub struct HBarBuilder {
+    /// The canvas the bars are drawn on, this will ultimately be turned to a string
+    canvas: Canvas,
+    /// Width of canvas
+    width: usize,
+    /// Height of canvas
+    height: usize,
+    /// The label of each bar
+    labels: Vec<String>,
+    /// The value of each bar
+    values: Vec<f64>,
+    /// Which order to draw the bars in
+    sort: HBarSort,
+    /// Whether or not to use color pixels
+    enable_color: bool,
+    /// Number of decimals in the values written after the bars
+    precision: usize,
+    /// Values and colors of the thresholds, bars at or above a threshold get its color
+    thresholds: Vec<(f64, Color)>,
+    /// The left and right edge of the bar of each row, in columns, and the value of the bar,
+    /// kept so the bars can be drawn again as rectangles in a SVG
+    extents: Vec<(f64, f64, f64)>,
+}
+
+impl HBarBuilder {
+    /// Create a new horizontal bar chart with desired width and height
+    ///
+    /// # Arguments
+    ///
+    /// * `labels` - The label of each bar
+    /// * `values` - The value of each bar, in the same order as the labels
+    /// * `width` - Width of the output canvas
+    /// * `height` - Maximum height of the output canvas, there is one row for each bar,
+    ///   bars that do not fit are left out
+    pub fn new(labels: &[String], values: &[f64], width: usize, height: usize) -> Self {
+        HBarBuilder {
+            canvas: Canvas::new(width, height),
+            width,
+            height,
+            labels: labels.to_vec(),
+            values: values.to_vec(),
+            sort: HBarSort::default(),
+            enable_color: true,
+            precision: 1,
+            thresholds: Vec::new(),
+            extents: Vec::new(),
+        }
+    }
+
+    /// Set the order of the bars
+    pub fn sort(&mut self, sort: HBarSort) -> &Self {
+        self.sort = sort;
+        self
+    }
+
+    /// Enable or disable color
+    pub fn color(&mut self, enable_color: bool) -> &Self {
+        self.enable_color = enable_color;
+        self
+    }
+
+    /// Set the number of decimals used in the values written after the bars
+    pub fn precision(&mut self, precision: usize) -> &Self {
+        self.precision = precision;
+        self
+    }
+
+    /// Add a threshold, bars with a value at or above it get the color of the threshold.
+    /// If a bar is above several thresholds, the color of the highest one is used.
+    ///
+    /// # Arguments
+    ///
+    /// * `value` - The value of the threshold
+    /// * `color` - The color of the bars at or above the threshold
+    pub fn threshold(&mut self, value: f64, color: Color) -> &Self {
+        self.thresholds.push((value, color));
+        self
+    }
+
+    /// Build the actual chart,
+    /// this will mutate &self, so only call it once.
+    pub fn build(&mut self) -> String {
+        let mut bars: Vec<(String, f64)> = self
+            .labels
+            .iter()
+            .cloned()
+            .zip(self.values.iter().cloned())
+            .collect();
+        match self.sort {
+            HBarSort::Input => (),
+            HBarSort::Ascending => bars.sort_by(|a, b| a.1.total_cmp(&b.1)),
+            HBarSort::Descending => bars.sort_by(|a, b| b.1.total_cmp(&a.1)),
+            HBarSort::Label => bars.sort_by(|a, b| a.0.cmp(&b.0)),
+        }
+        bars.truncate(self.height);
+        // Do not leave empty rows under the bars
+        self.height = bars.len();
+        self.canvas.resize(self.width, self.height);
+
+        let values: Vec<String> = bars
+            .iter()
+            .map(|(_, value)| format_si(*value, self.precision))
+            .collect();
+        let mut value_width = values.iter().map(|v| display_width(v)).max().unwrap_or(0);
+        // The values are never cut short, they are left out if there is no room for them
+        let show_values = value_width + 2 <= self.width;
+        if !show_values {
+            value_width = 0;
+        }
+
+        // Labels may use up to a third of the width, longer labels are truncated
+        let label_width = bars
+            .iter()
+            .map(|(label, _)| display_width(label))
+            .max()
+            .unwrap_or(0)
+            .min(self.width / 3)
+            .min(self.width.saturating_sub(value_width + 2));
+
+        // Room for the label, a space, the bar, a space and the value
+        let bar_width = self.width.saturating_sub(label_width + 1 + 1 + value_width);
+
+        // Negative bars grow to the left of zero, positive to the right
+        let negative = bars.iter().map(|(_, value)| -value).fold(0.0, f64::max);
+        let positive = bars.iter().map(|(_, value)| *value).fold(0.0, f64::max);
+        let zero = if negative + positive > 0.0 {
+            (bar_width as f64 * negative / (negative + positive)).round() as usize
+        } else {
+            0
+        };
+        let eighths = if negative + positive > 0.0 {
+            (bar_width * 8) as f64 / (negative + positive)
+        } else {
+            0.0
+        };
+
+        for (row, ((label, value), value_text)) in bars.iter().zip(values.iter()).enumerate() {
+            self.write(0, row, &truncate(label, label_width));
+
+            let color = self.bar_color(*value);
+            let bar_start = label_width + 1;
+            let length = (value.abs() * eighths).round() as usize;
+            let zero_column = (bar_start + zero) as f64;
+            let text_column = if value.is_nan() {
+                self.extents.push((zero_column, zero_column, *value));
+                bar_start + zero
+            } else if *value >= 0.0 {
+                let length = length.min((bar_width - zero) * 8);
+                let end = zero_column + length as f64 / 8.0;
+                self.extents.push((zero_column, end, *value));
+                for column in 0..length / 8 {
+                    self.draw_bar(bar_start + zero + column, row, BAR_RIGHT[7], color);
+                }
+                if !length.is_multiple_of(8) {
+                    self.draw_bar(
+                        bar_start + zero + length / 8,
+                        row,
+                        BAR_RIGHT[length % 8 - 1],
+                        color,
+                    );
+                }
+                bar_start + zero + length.div_ceil(8) + 1
+            } else {
+                // Only the right 1/8, 1/2 and 8/8 of a character have blocks,
+                // so the left end is less precise
+                let length = length.min(zero * 8);
+                let start = zero_column - length as f64 / 8.0;
+                self.extents.push((start, zero_column, *value));
+                for column in 0..length / 8 {
+                    self.draw_bar(bar_start + zero - column - 1, row, BAR_RIGHT[7], color);
+                }
+                let partial = match length % 8 {
+                    0 => None,
+                    1 | 2 => Some('▕'),
+                    3..=5 => Some('▐'),
+                    _ => Some('█'),
+                };
+                if let Some(partial) = partial {
+                    self.draw_bar(bar_start + zero - length / 8 - 1, row, partial, color);
+                }
+                bar_start + zero + 1
+            };
+            if show_values {
+                self.write(text_column, row, value_text);
+            }
+        }
+
+        self.to_string()
+    }
+
+    /// Build the bar chart as a SVG image, with the same layout as build.
+    /// The bars are drawn as rectangles, the labels and values as text.
+    pub fn build_svg(&mut self) -> String {
+        self.build();
+        let mut svg = Svg::new(self.width, self.height);
+        // The cells of the bars are left out, they are drawn again as rectangles
+        svg.cells(&self.canvas, |x, y| {
+            self.extents
+                .get(y)
+                .is_some_and(|(start, end, _)| x as f64 >= start.floor() && (x as f64) < end.ceil())
+        });
+        let bar_height = 0.8 * svg::CELL_HEIGHT;
+        for (row, (start, end, value)) in self.extents.iter().enumerate() {
+            // Bars shorter than half an eighth of a character are not drawn by build either
+            if end - start < 1.0 / 16.0 {
+                continue;
+            }
+            let px = if self.enable_color {
+                self.bar_color(*value)(Pixel::Char(' '))
+            } else {
+                GraphPixel::Normal(Pixel::Char(' '))
+            };
+            svg.rect(
+                start * svg::CELL_WIDTH,
+                (row as f64 + 0.1) * svg::CELL_HEIGHT,
+                (end - start) * svg::CELL_WIDTH,
+                bar_height,
+                svg::color(&px),
+            );
+        }
+        svg.to_string()
+    }
+
+    /// The color of a bar, the color of the highest threshold at or below its value,
+    /// or the first series color
+    ///
+    /// # Arguments
+    ///
+    /// * `value` - The value of the bar
+    fn bar_color(&self, value: f64) -> fn(Pixel) -> GraphPixel<Pixel> {
+        self.thresholds
+            .iter()
+            .filter(|(threshold, _)| value >= *threshold)
+            .max_by(|a, b| a.0.total_cmp(&b.0))
+            .map(|(_, color)| color.pixel())
+            .unwrap_or(SERIES_COLORS[0])
+    }
+
+    /// Set a pixel of a bar
+    ///
+    /// # Arguments
+    ///
+    /// * `x` - X-position of pixel
+    /// * `y` - Y-position of pixel
+    /// * `c` - The block character to set
+    /// * `color` - The color of the bar
+    fn draw_bar(&mut self, x: usize, y: usize, c: char, color: fn(Pixel) -> GraphPixel<Pixel>) {
+        let px = if self.enable_color {
+            color(Pixel::Char(c))
+        } else {
+            GraphPixel::Normal(Pixel::Char(c))
+        };
+        self.canvas.set(x, y, px);
+    }
+
+    /// Write text to the canvas, starting at a position, text outside the canvas is left out
+    ///
+    /// # Arguments
+    ///
+    /// * `x` - X-position of the first column of the text
+    /// * `y` - Y-position of the text
+    /// * `text` - The text to write
+    fn write(&mut self, x: usize, y: usize, text: &str) {
+        self.canvas.text(x, y, text, GraphPixel::Normal);
+    }
+}
+
+impl std::fmt::Display for HBarBuilder {
+    /// Turn canvas into a string
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{}", self.canvas)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A chart of `values` with the labels `labels`, drawn without colors
+    ///
+    /// # Arguments
+    ///
+    /// * `labels` - The label of each bar
+    /// * `values` - The value of each bar
+    /// * `width` - Width of the canvas
+    /// * `height` - Height of the canvas
+    fn hbar(labels: &[&str], values: &[f64], width: usize, height: usize) -> HBarBuilder {
+        let labels: Vec<String> = labels.iter().map(|label| label.to_string()).collect();
+        let mut hbar = HBarBuilder::new(&labels, values, width, height);
+        hbar.color(false);
+        hbar
+    }
+
+    #[test]
+    fn bars_end_in_eighths_and_are_followed_by_their_value() {
+        let mut hbar = hbar(&["a", "bb", "a very long label"], &[1.0, 4.0, 2.5], 20, 5);
+        assert_eq!(
+            hbar.build(),
+            "a      ██▎ 1        \nbb     █████████ 4  \na ver… █████▋ 2.5   "
+        );
+    }
+
+    #[test]
+    fn sorted_bars_that_do_not_fit_are_left_out() {
+        let labels = ["a", "bb", "c"];
+        let values = [1.0, 4.0, 2.5];
+        let mut descending = hbar(&labels, &values, 12, 2);
+        descending.sort(HBarSort::Descending);
+        assert_eq!(descending.build(), "bb █████ 4  \nc  ███▏ 2.5 ");
+
+        let mut by_label = hbar(&["b", "c", "a"], &values, 12, 5);
+        by_label.sort(HBarSort::Label);
+        let labels: Vec<String> = by_label
+            .build()
+            .lines()
+            .map(|line| line[..1].to_string())
+            .collect();
+        assert_eq!(labels, ["a", "b", "c"]);
+    }
+
+    #[test]
+    fn negative_bars_grow_to_the_left() {
+        let mut hbar = hbar(&["up", "down"], &[2.0, -1.0], 16, 5);
+        assert_eq!(hbar.build(), "up      █████ 2 \ndown ▐██ -1     ");
+    }
+
+    #[test]
+    fn bars_get_the_color_of_the_highest_threshold_below_them() {
+        let mut hbar = hbar(&["a", "b", "c"], &[1.0, 5.0, 9.0], 20, 5);
+        hbar.color(true);
+        hbar.threshold(4.0, Color::Yellow);
+        hbar.threshold(8.0, Color::Red);
+        let chart = hbar.build();
+        let rows: Vec<&str> = chart.lines().collect();
+        assert!(rows[0].contains("\u{1b}[32m"));
+        assert!(rows[1].contains("\u{1b}[33m") && !rows[1].contains("\u{1b}[31m"));
+        assert!(rows[2].contains("\u{1b}[31m") && !rows[2].contains("\u{1b}[33m"));
+    }
+
+    #[test]
+    fn svg_draws_bars_as_rectangles() {
+        let svg = hbar(&["a", "b&c"], &[1.0, 2.0], 12, 5).build_svg();
+        assert!(svg.contains(r#"<rect x="40.0" y="2.0" width="30.0" height="16.0" fill="black"/>"#));
+        assert!(
+            svg.contains(r#"<rect x="40.0" y="22.0" width="60.0" height="16.0" fill="black"/>"#)
+        );
+        assert!(svg.contains(">b&amp;c</text>"));
+        // The blocks of the bars are not written as text as well
+        assert!(!svg.contains('█'));
+    }
+}
@@ -0,0 +1,171 @@
+use crate::ticks::{half_span, nice_step};
+
+/// How values should be divided into bins for a histogram
+#[derive(PartialEq, Clone, Default)]
+pub enum Binning {
+    /// Pick a nice bin width from the spread and number of values
+    #[default]
+    Auto,
+    /// Use this many bins of equal width
+    Count(usize),
+    /// Use bins of this width, starting at a multiple of the width
+    Width(f64),
+}
+
+/// Find the edges of the bins of a histogram of `values`,
+/// there is one more edge than there are bins.
+/// Values that are not finite are ignored.
+///
+/// # Arguments
+///
+/// * `values` - The values that should be counted in the histogram
+/// * `binning` - How to decide the bins
+/// * `max_bins` - The largest number of bins to use, a wider bin is used if there would be more
+pub fn bin_edges(values: &[f64], binning: &Binning, max_bins: usize) -> Vec<f64> {
+    let finite: Vec<f64> = values.iter().cloned().filter(|v| v.is_finite()).collect();
+    let min = finite.iter().cloned().fold(f64::INFINITY, f64::min);
+    let max = finite.iter().cloned().fold(f64::NEG_INFINITY, f64::max);
+    let max_bins = max_bins.max(1);
+    if finite.is_empty() {
+        return Vec::new();
+    }
+    if min == max {
+        // Every value is the same, use a single bin around it
+        return vec![min - 0.5, max + 0.5];
+    }
+    if (max - min).is_infinite() {
+        // The bins of half the values are found instead, they are doubled afterwards
+        let halves: Vec<f64> = finite.iter().map(|v| v / 2.0).collect();
+        let binning = match binning {
+            Binning::Width(width) => Binning::Width(width / 2.0),
+            binning => binning.clone(),
+        };
+        return bin_edges(&halves, &binning, max_bins)
+            .into_iter()
+            .map(|edge| (edge * 2.0).clamp(f64::MIN, f64::MAX))
+            .collect();
+    }
+
+    let (start, width, count) = match binning {
+        Binning::Count(count) => {
+            let count = (*count).clamp(1, max_bins);
+            (min, (max - min) / count as f64, count)
+        }
+        _ => {
+            let width = match binning {
+                Binning::Width(width) if *width > 0.0 => *width,
+                _ => nice_step(
+                    max - min,
+                    auto_bin_count(&finite, min, max).clamp(1, max_bins),
+                ),
+            };
+            let start = (min / width).floor() * width;
+            // The number of bins to reach max, without subtracting values that may be huge
+            let bins = |width: f64| (max / width - start / width).floor() as usize + 1;
+            // Merge neighbouring bins if there are too many of them
+            let merge = bins(width).div_ceil(max_bins);
+            let width = width * merge as f64;
+            (start, width, bins(width))
+        }
+    };
+
+    (0..=count).map(|i| start + i as f64 * width).collect()
+}
+
+/// Count how many of `values` fall in each bin, see bin_edges.
+/// Each bin includes its lower edge, the last bin also includes its upper edge.
+/// The counts are returned as f64, so they can be graphed directly.
+///
+/// # Arguments
+///
+/// * `values` - The values that should be counted
+/// * `edges` - The edges of the bins
+pub fn bin_counts(values: &[f64], edges: &[f64]) -> Vec<f64> {
+    let bins = edges.len().saturating_sub(1);
+    let mut counts = vec![0.0; bins];
+    if bins == 0 {
+        return counts;
+    }
+    let start = edges[0];
+    let half_width = half_span(start, edges[bins]) / bins as f64;
+    for value in values.iter().filter(|v| v.is_finite()) {
+        if *value < start || *value > edges[bins] {
+            continue;
+        }
+        let bin = ((half_span(start, *value) / half_width).floor() as usize).min(bins - 1);
+        counts[bin] += 1.0;
+    }
+    counts
+}
+
+/// Number of bins given by the larger of the Freedman–Diaconis rule and Sturges' rule,
+/// Sturges' rule handles small inputs, Freedman–Diaconis large inputs with outliers.
+///
+/// # Arguments
+///
+/// * `values` - The finite values of the histogram
+/// * `min` - The smallest value
+/// * `max` - The largest value
+fn auto_bin_count(values: &[f64], min: f64, max: f64) -> usize {
+    let mut sorted = values.to_vec();
+    sorted.sort_by(|a, b| a.total_cmp(b));
+    let quartile = |q: f64| sorted[((sorted.len() - 1) as f64 * q).round() as usize];
+    let iqr = quartile(0.75) - quartile(0.25);
+    let sturges = (sorted.len() as f64).log2().ceil() as usize + 1;
+    if iqr <= 0.0 {
+        return sturges;
+    }
+    let width = 2.0 * iqr / (sorted.len() as f64).cbrt();
+    (((max - min) / width).ceil() as usize).max(sturges)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bin_edges_of_count() {
+        let values = [0.0, 3.0, 8.0];
+        assert_eq!(
+            bin_edges(&values, &Binning::Count(4), 10),
+            vec![0.0, 2.0, 4.0, 6.0, 8.0]
+        );
+        assert_eq!(
+            bin_edges(&values, &Binning::Count(10), 2),
+            vec![0.0, 4.0, 8.0]
+        );
+    }
+
+    #[test]
+    fn bin_edges_of_width() {
+        let values = [1.0, 10.0];
+        assert_eq!(
+            bin_edges(&values, &Binning::Width(3.0), 10),
+            vec![0.0, 3.0, 6.0, 9.0, 12.0]
+        );
+        // Neighbouring bins are merged when there are too many
+        assert_eq!(
+            bin_edges(&values, &Binning::Width(3.0), 2),
+            vec![0.0, 6.0, 12.0]
+        );
+    }
+
+    #[test]
+    fn bin_edges_auto_are_nice() {
+        let values: Vec<f64> = (0..=100).map(|v| v as f64).collect();
+        let edges = bin_edges(&values, &Binning::Auto, 50);
+        assert!(edges.len() > 2);
+        assert!(edges[0] <= 0.0 && edges[edges.len() - 1] >= 100.0);
+        let width = edges[1] - edges[0];
+        assert_eq!(width, 20.0);
+        assert!(edges.iter().all(|edge| edge % width == 0.0));
+    }
+
+    #[test]
+    fn bin_counts_include_last_edge() {
+        let edges = [0.0, 2.0, 4.0];
+        let values = [0.0, 1.9, 2.0, 4.0, 5.0, f64::NAN];
+        assert_eq!(bin_counts(&values, &edges), vec![2.0, 2.0]);
+        assert!(bin_counts(&values, &[1.0]).is_empty());
+    }
+}
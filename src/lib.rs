@@ -1,4 +1,14 @@
+pub mod canvas;
+pub mod downsample;
 pub mod graph;
+pub mod hbar;
+pub mod histogram;
 pub mod parseopts;
+pub mod stats;
+pub mod svg;
 #[cfg(feature = "libc")]
 pub mod term;
+#[cfg(test)]
+mod testing;
+pub mod text;
+pub mod ticks;
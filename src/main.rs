@@ -1,10 +1,8 @@
-use std::io::{self, BufRead};
+use std::io::{self, BufRead, IsTerminal, Write};
 use std::str::FromStr;
-use textgraph::graph::GraphBuilder;
-use textgraph::parseopts::{parseopts, OptsBuilder};
-
-#[cfg(all(feature = "libc", feature = "ansi"))]
-use std::io::Write;
+use textgraph::graph::{GraphBuilder, GraphError, GraphType, YAxis};
+use textgraph::hbar::HBarBuilder;
+use textgraph::parseopts::{parseopts, Mode, OptsBuilder};
 
 #[cfg(all(feature = "libc", feature = "ansi"))]
 extern "C" fn handle_sigint(_sig: std::os::raw::c_int) {
@@ -20,7 +18,7 @@ fn set_filter_signalhandler() {
     unsafe {
         let mut action: textgraph::term::SigAction = std::mem::zeroed();
         action.sa_flags = 0;
-        action.sa_sigaction = handle_sigint as usize;
+        action.sa_sigaction = handle_sigint as *const () as usize;
 
         textgraph::term::sigemptyset(&mut action.sa_mask);
         textgraph::term::sigaction(15, &action, std::ptr::null_mut()); // 15 is SIGTERM
@@ -31,43 +29,292 @@ fn set_filter_signalhandler() {
     }
 }
 
+/// How much of the difference to the range of the values the y-axis shrinks by
+/// for each new sample, when --sticky is used
+const STICKY_DECAY: f64 = 0.05;
+
+/// Samples read from the input, each column of the input is a series
+#[derive(Default)]
+struct Samples {
+    /// Names of the series, taken from the header line if there is one
+    names: Vec<String>,
+    /// The x value of each line
+    x_values: Vec<f64>,
+    /// The values of each series
+    y_values: Vec<Vec<f64>>,
+    /// The label of each line, when the input is labelled values
+    labels: Vec<String>,
+}
+
+impl Samples {
+    /// Parse a line of input, and add the values to the series.
+    /// Columns can be separated by whitespace or commas.
+    /// If the first line cannot be parsed as numbers, it is used as the names of the series.
+    ///
+    /// # Arguments
+    ///
+    /// * `line` - The line to parse
+    /// * `x` - The x value of the line, used if `x_column` is false
+    /// * `x_column` - Whether the first column of the line is the x value, if there are more
+    fn push_line(&mut self, line: &str, x: f64, x_column: bool) -> Result<(), ()> {
+        let columns: Vec<&str> = line
+            .split(|c: char| c.is_whitespace() || c == ',')
+            .filter(|c| !c.is_empty())
+            .collect();
+        if columns.is_empty() {
+            return Err(());
+        }
+
+        let Ok(mut values) = columns
+            .iter()
+            .map(|c| f64::from_str(c))
+            .collect::<Result<Vec<f64>, _>>()
+        else {
+            if self.x_values.is_empty() && self.names.is_empty() {
+                self.names = columns.iter().map(|c| c.to_string()).collect();
+                return Ok(());
+            }
+            return Err(());
+        };
+
+        // Lines with a single column have no x value, the line number is used instead
+        let x = if x_column && values.len() > 1 {
+            values.remove(0)
+        } else {
+            x
+        };
+
+        if self.y_values.is_empty() {
+            self.y_values = vec![Vec::new(); values.len()];
+        }
+        if values.len() != self.y_values.len() {
+            return Err(());
+        }
+        for (series, y) in self.y_values.iter_mut().zip(values) {
+            series.push(y);
+        }
+        self.x_values.push(x);
+        Ok(())
+    }
+}
+
+impl Samples {
+    /// Parse a line of input consisting of a label and a value, such as the output of du.
+    /// The value can be either the last or the first column of the line,
+    /// the rest of the line is the label.
+    ///
+    /// # Arguments
+    ///
+    /// * `line` - The line to parse
+    fn push_labelled_line(&mut self, line: &str) -> Result<(), ()> {
+        let is_separator = |c: char| c.is_whitespace() || c == ',';
+        let line = line.trim();
+        if let Some((label, value)) = line.rsplit_once(is_separator) {
+            if let Ok(value) = f64::from_str(value) {
+                self.labels
+                    .push(label.trim_end_matches(is_separator).to_string());
+                self.y_values.resize(1, Vec::new());
+                self.y_values[0].push(value);
+                return Ok(());
+            }
+        }
+        if let Some((value, label)) = line.split_once(is_separator) {
+            if let Ok(value) = f64::from_str(value) {
+                self.labels
+                    .push(label.trim_start_matches(is_separator).to_string());
+                self.y_values.resize(1, Vec::new());
+                self.y_values[0].push(value);
+                return Ok(());
+            }
+        }
+        Err(())
+    }
+
+    /// Parse a line of input, in the format the mode of the options expects
+    ///
+    /// # Arguments
+    ///
+    /// * `line` - The line to parse
+    /// * `x` - The x value of the line
+    /// * `opts` -  textgraph::parseopts::OptBuilder
+    fn push(&mut self, line: &str, x: f64, opts: &OptsBuilder) -> Result<(), ()> {
+        match opts.mode {
+            Mode::Graph | Mode::Histogram => self.push_line(line, x, opts.x_column),
+            Mode::HBar => self.push_labelled_line(line),
+        }
+    }
+
+    /// Parse all lines of an input, the x value of each line is its line number
+    ///
+    /// # Arguments
+    ///
+    /// * `input` - The lines to parse
+    /// * `opts` -  textgraph::parseopts::OptBuilder
+    fn parse(input: &str, opts: &OptsBuilder) -> Self {
+        let mut samples = Samples::default();
+        for (i, line) in input.lines().enumerate() {
+            samples.push(line, i as f64, opts).expect("TG7");
+        }
+        samples
+    }
+}
+
+/// Build a horizontal bar chart text string, based on labelled values and a OptsBuilder
+///
+/// # Arguments
+///
+/// * `samples` - The labelled values to graph
+/// * `opts` -  textgraph::parseopts::OptBuilder
+/// * `svg` - Build the chart as a SVG image instead of text
+fn build_hbar(samples: &Samples, opts: &OptsBuilder, svg: bool) -> String {
+    let opts = opts.clone().build();
+
+    let values = samples.y_values.first().cloned().unwrap_or_default();
+    let mut hb = HBarBuilder::new(&samples.labels, &values, opts.width, opts.height);
+    hb.color(opts.color);
+    hb.sort(opts.sort.clone());
+    hb.precision(opts.precision);
+    for (value, color) in &opts.thresholds {
+        hb.threshold(*value, color.clone());
+    }
+    if svg {
+        hb.build_svg()
+    } else {
+        hb.build()
+    }
+}
+
 /// Build a graph text string, based on values and a OptsBuilder
 ///
 /// # Arguments
 ///
+/// * `samples` - The samples to graph
 /// * `opts` -  textgraph::parseopts::OptBuilder
-fn build_graph(x_values: &Vec<f64>, y_values: &Vec<f64>, opts: &OptsBuilder) -> String {
+/// * `y_range` - The range of the y-axis of the previous graph, used by --sticky,
+///   it is updated to the range of the new graph
+/// * `svg` - Build the graph as a SVG image instead of text
+/// * `filter` - Whether the graph is redrawn for each line of input, see filter
+fn build_graph(
+    samples: &Samples,
+    opts: &OptsBuilder,
+    y_range: &mut Option<(f64, f64)>,
+    svg: bool,
+    filter: bool,
+) -> Result<String, GraphError> {
+    if opts.mode == Mode::HBar {
+        return Ok(build_hbar(samples, opts, svg));
+    }
     let opts = opts.clone().build();
 
-    let mut gb = GraphBuilder::new(&x_values, &y_values, opts.width, opts.height);
+    let Some(first) = samples.y_values.first() else {
+        return Ok(String::new());
+    };
+    // The header also names the x column, if there is one
+    let name_offset = if opts.x_column { 1 } else { 0 };
+    let mut gb = if opts.mode == Mode::Histogram {
+        GraphBuilder::histogram(first, opts.binning.clone(), opts.width, opts.height)
+    } else {
+        GraphBuilder::new(&samples.x_values, first, opts.width, opts.height)
+    };
+    if let Some(name) = samples.names.get(name_offset) {
+        gb.name(name);
+    }
+    for (i, values) in samples.y_values.iter().enumerate().skip(1) {
+        let name = match samples.names.get(i + name_offset) {
+            Some(name) => name.clone(),
+            None => format!("{}", i + 1),
+        };
+        gb.add_series(&name, values);
+    }
     gb.color(opts.color);
     gb.axis(!opts.silent);
-    gb.graph_type(opts.graph_type.clone());
+    if opts.mode != Mode::Histogram {
+        gb.graph_type(opts.graph_type.clone());
+    }
+    gb.max_gap(opts.max_gap);
+    gb.x_format(opts.x_format.clone());
+    gb.precision(opts.precision);
+    gb.y_bounds(opts.y_min, opts.y_max);
+    // The bars of a histogram always start at zero
+    gb.include_zero(opts.include_zero || opts.mode == Mode::Histogram);
+    gb.y_scale(opts.y_scale.clone());
+    gb.spark_values(opts.spark_values);
+    gb.annotate(opts.annotate);
+    // Only useful when the graph follows the input
+    gb.edge_value(opts.annotate && filter);
+    gb.footer(opts.stats);
+    gb.legend(opts.legend);
+    gb.grid(opts.grid.0, opts.grid.1);
+    if let Some(title) = &opts.title {
+        gb.title(title);
+    }
+    if let Some(label) = &opts.x_label {
+        gb.x_label(label);
+    }
+    if let Some(label) = &opts.y_label {
+        gb.y_label(label);
+    }
+    for series in &opts.right {
+        // A series is either given by its number, or by its name in the header
+        let g = match usize::from_str(series) {
+            Ok(n) => n.checked_sub(1),
+            Err(_) => samples
+                .names
+                .iter()
+                .skip(name_offset)
+                .position(|name| name == series),
+        };
+        if let Some(g) = g {
+            gb.series_axis(g, YAxis::Right);
+        }
+    }
+    if let Some(label) = &opts.right_label {
+        gb.right_y_label(label);
+    }
+    gb.downsample(opts.downsample.clone());
+    for (value, color) in &opts.thresholds {
+        gb.threshold(*value, color.clone());
+    }
+    if let (true, Some(previous)) = (opts.sticky, *y_range) {
+        gb.sticky_range(previous, STICKY_DECAY);
+    }
     if opts.cut {
         gb.cut_overflow(true);
     } else if let Some(n) = opts.last_n {
         gb.keep_tail(n as usize);
     }
 
-    gb.build()
+    let graph = if svg {
+        gb.try_build_svg()?
+    } else {
+        gb.try_build()?
+    };
+    *y_range = Some(gb.y_range());
+    Ok(graph)
 }
 
 /// Will graph what comes in through stdin,
 /// For each new line, the graph will be re-drawn.
+/// Sparklines are redrawn in place when writing to a terminal, instead of using the
+/// alternate screen, otherwise each new sparkline is written on a new line.
 ///
 /// # Arguments
 ///
 /// * `opts` -  textgraph::parseopts::OptBuilder
 fn filter(opts: OptsBuilder) {
+    let sparkline = opts.mode == Mode::Graph && opts.graph_type == GraphType::Spark;
+    let in_place = cfg!(feature = "ansi") && sparkline && io::stdout().is_terminal();
+    let mut lines_drawn = 0;
+
     #[cfg(all(feature = "libc", feature = "ansi"))]
-    {
+    if !sparkline {
         set_filter_signalhandler();
         print!("\x1b[?1049h");
         print!("\x1b[?25l");
     }
 
-    let mut x_values: Vec<f64> = Vec::new();
-    let mut y_values: Vec<f64> = Vec::new();
+    let mut samples = Samples::default();
+    let mut y_range = None;
     let mut i = 0.0;
 
     let stdin = io::stdin();
@@ -75,47 +322,96 @@ fn filter(opts: OptsBuilder) {
         i += 1.0;
         let line = line.expect("Could not read...");
 
-        let y = f64::from_str(line.as_str());
-        if let Err(_) = y {
+        if samples.push(&line, i, &opts).is_err() {
             print!("Could not parse line as f64.");
             continue;
         }
-        if let Ok(y) = y {
-            y_values.push(y);
-            x_values.push(i);
+        if samples.y_values.is_empty() {
+            continue;
         }
 
-        #[cfg(feature = "ansi")]
-        print!("\x1B[2J\x1B[H");
-        println!("{}", build_graph(&x_values, &y_values, &opts));
+        // The error is shown instead of the graph, the next line may fix it
+        let graph = build_graph(&samples, &opts, &mut y_range, false, true)
+            .unwrap_or_else(|e| e.to_string());
+        if in_place {
+            // Move back to the start of the previous sparkline, and clear it
+            if lines_drawn > 1 {
+                print!("\x1B[{}A", lines_drawn - 1);
+            }
+            print!("\r\x1B[J{}", graph);
+            io::stdout().flush().expect("TG8");
+            lines_drawn = graph.lines().count();
+        } else if sparkline {
+            println!("{}", graph);
+        } else {
+            #[cfg(feature = "ansi")]
+            print!("\x1B[2J\x1B[H");
+            println!("{}", graph);
+        }
+    }
+    if lines_drawn > 0 {
+        println!();
     }
 }
 
 /// Will graph the contents of a file
 /// This assumes opts.in_file is Some, or it will panic!
-/// Currently this only supports a single column, with no x-values
+/// Each column of the file is graphed as a separate series,
+/// the x values are the line numbers, unless the first column is used as x values
 ///
 /// # Arguments
 ///
 /// * `opts` -  textgraph::parseopts::OptBuilder
 fn graph_file(opts: OptsBuilder) {
     let raw_y_values = std::fs::read_to_string(opts.in_file.clone().unwrap()).expect("TG6");
+    let samples = Samples::parse(&raw_y_values, &opts);
 
-    let mut y_values: Vec<f64> = Vec::new();
-    let mut x_values: Vec<f64> = Vec::new();
-    for (i, line) in raw_y_values.lines().enumerate() {
-        y_values.push(f64::from_str(line).expect("TG7"));
-        x_values.push(i as f64);
+    match build_graph(&samples, &opts, &mut None, false, false) {
+        Ok(graph) => println!("{}", graph),
+        Err(e) => {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        }
     }
+}
+
+/// Will write the graph of the whole input as a SVG image to the file of opts.output,
+/// the input is read from opts.in_file, or from stdin until it ends.
+/// This assumes opts.output is Some, or it will panic!
+/// Colors are enabled, unless they are disabled with --color no.
+///
+/// # Arguments
+///
+/// * `opts` -  textgraph::parseopts::OptBuilder
+fn graph_svg(mut opts: OptsBuilder) {
+    let input = match &opts.in_file {
+        Some(in_file) => std::fs::read_to_string(in_file).expect("TG6"),
+        None => io::read_to_string(io::stdin()).expect("TG9"),
+    };
+    let samples = Samples::parse(&input, &opts);
+    opts.color = opts.color.or(Some(true));
 
-    println!("{}", build_graph(&x_values, &y_values, &opts));
+    let svg = match build_graph(&samples, &opts, &mut None, true, false) {
+        Ok(svg) => svg,
+        Err(e) => {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        }
+    };
+    let output = opts.output.clone().unwrap();
+    if let Err(e) = std::fs::write(&output, svg + "\n") {
+        eprintln!("Could not write {}: {}", output, e);
+        std::process::exit(1);
+    }
 }
 
 /// Main entry point for the binary of textgraph
 fn main() {
     let opts = parseopts();
 
-    if opts.in_file.is_none() {
+    if opts.output.is_some() {
+        graph_svg(opts);
+    } else if opts.in_file.is_none() {
         filter(opts);
     } else {
         graph_file(opts);
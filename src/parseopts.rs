@@ -1,8 +1,22 @@
-use crate::graph::{BrailleType, GraphType};
+use crate::downsample::Downsample;
+use crate::graph::{AreaFill, BlockType, BrailleType, Color, GraphType, XFormat, YScale};
+use crate::hbar::HBarSort;
+use crate::histogram::Binning;
 use std::str::FromStr;
 
 use std::io::IsTerminal;
 
+/// What kind of chart the input should be drawn as
+#[derive(PartialEq, Clone)]
+pub enum Mode {
+    /// Each column of the input is a series of samples, drawn by GraphBuilder
+    Graph,
+    /// Each line of the input is a label and a value, drawn by HBarBuilder
+    HBar,
+    /// The distribution of the values of the first column, drawn by GraphBuilder::histogram
+    Histogram,
+}
+
 /// Struct containing command line options
 pub struct Opts {
     /// Desired width of graph, if None, it should be automatically determined
@@ -21,6 +35,56 @@ pub struct Opts {
     pub in_file: Option<String>,
     /// Enable color
     pub color: bool,
+    /// Use the first column of the input as x values
+    pub x_column: bool,
+    /// Largest distance between two x values that should be joined in the graph
+    pub max_gap: Option<f64>,
+    /// How the x values are written in the x-axis labels
+    pub x_format: XFormat,
+    /// Number of decimals in the axis labels
+    pub precision: usize,
+    /// Fixed lower bound of the y-axis
+    pub y_min: Option<f64>,
+    /// Fixed upper bound of the y-axis
+    pub y_max: Option<f64>,
+    /// Always include zero on the y-axis
+    pub include_zero: bool,
+    /// Only let the y-axis shrink slowly between redraws
+    pub sticky: bool,
+    /// How values are placed along the y-axis
+    pub y_scale: YScale,
+    /// What kind of chart to draw
+    pub mode: Mode,
+    /// Order of the bars in a horizontal bar chart
+    pub sort: HBarSort,
+    /// How to divide the values into bins for a histogram
+    pub binning: Binning,
+    /// Whether the min, max and last value are written after a sparkline
+    pub spark_values: bool,
+    /// How samples are combined when there are more samples than columns
+    pub downsample: Downsample,
+    /// Values to draw a horizontal line at, and the color of the line
+    pub thresholds: Vec<(f64, Color)>,
+    /// Whether the min, max and last value are written next to the graph
+    pub annotate: bool,
+    /// Whether a line of statistics is written under the graph
+    pub stats: bool,
+    /// Text written above the graph
+    pub title: Option<String>,
+    /// Text written under the x-axis
+    pub x_label: Option<String>,
+    /// Text written left of the y-axis
+    pub y_label: Option<String>,
+    /// Series drawn against the right y-axis, by number starting at 1 or by name
+    pub right: Vec<String>,
+    /// Text written right of the right y-axis
+    pub right_label: Option<String>,
+    /// Whether the names of the series are shown in a box next to the graph
+    pub legend: bool,
+    /// Whether gridlines are drawn at the y-axis ticks and at the x-axis ticks
+    pub grid: (bool, bool),
+    /// Write the graph of the whole input to this file as SVG, instead of drawing it as text
+    pub output: Option<String>,
 }
 
 /// Struct containing command line options
@@ -34,6 +98,31 @@ pub struct OptsBuilder {
     pub cut: bool,
     pub in_file: Option<String>,
     pub color: Option<bool>,
+    pub x_column: bool,
+    pub max_gap: Option<f64>,
+    pub x_format: XFormat,
+    pub precision: usize,
+    pub y_min: Option<f64>,
+    pub y_max: Option<f64>,
+    pub include_zero: bool,
+    pub sticky: bool,
+    pub y_scale: YScale,
+    pub mode: Mode,
+    pub sort: HBarSort,
+    pub binning: Binning,
+    pub spark_values: bool,
+    pub downsample: Downsample,
+    pub thresholds: Vec<(f64, Color)>,
+    pub annotate: bool,
+    pub stats: bool,
+    pub title: Option<String>,
+    pub x_label: Option<String>,
+    pub y_label: Option<String>,
+    pub right: Vec<String>,
+    pub right_label: Option<String>,
+    pub legend: bool,
+    pub grid: (bool, bool),
+    pub output: Option<String>,
 }
 
 impl OptsBuilder {
@@ -53,7 +142,11 @@ impl OptsBuilder {
                     std::process::exit(1);
                 }
             }),
+            // A sparkline is a single line for each series, so the height is not used
             height: self.height.unwrap_or_else(|| {
+                if self.graph_type == GraphType::Spark {
+                    return 1;
+                }
                 #[cfg(feature = "libc")]
                 if let Ok((_, height)) = crate::term::get_terminal_size() {
                     height as usize - 1
@@ -75,6 +168,31 @@ impl OptsBuilder {
             color: self
                 .color
                 .unwrap_or_else(|| std::io::stdout().is_terminal()),
+            x_column: self.x_column,
+            max_gap: self.max_gap,
+            x_format: self.x_format,
+            precision: self.precision,
+            y_min: self.y_min,
+            y_max: self.y_max,
+            include_zero: self.include_zero,
+            sticky: self.sticky,
+            y_scale: self.y_scale,
+            mode: self.mode,
+            sort: self.sort,
+            binning: self.binning,
+            spark_values: self.spark_values,
+            downsample: self.downsample,
+            thresholds: self.thresholds,
+            annotate: self.annotate,
+            stats: self.stats,
+            title: self.title,
+            x_label: self.x_label,
+            y_label: self.y_label,
+            right: self.right,
+            right_label: self.right_label,
+            legend: self.legend,
+            grid: self.grid,
+            output: self.output,
         }
     }
 }
@@ -95,13 +213,49 @@ macro_rules! parseopts_panic {
         println!("  -b, --braille       Shorthand for -t braille");
         println!("  -a, --ascii         Shorthand for -t ascii");
         println!(
-            "  -t           TYPE   Set graph type, valid options are 'star', 'ascii', 'braille', 'braille6' or 'braille8'"
+            "  -t           TYPE   Set graph type, valid options are 'star', 'ascii', 'braille', 'braille6', 'braille8', 'half', 'quadrant', 'bar', 'area', 'spark', 'scatter', 'hbar' or 'hist'"
         );
         println!("  -n, --last-n N      Only include N samples in plot");
         println!("  -c, --cut           Special case of --last-n, where N is set to --width");
         println!("  -w, --width  WIDTH  Set desired width of graph");
         println!("  -h, --height HEIGHT Set desired height of graph");
         println!("      --color  ENABLE Enable or disable color, valid options are 'yes' or 'no'");
+        println!("  -x, --x-column      Use the first column of the input as x values");
+        println!("      --max-gap GAP   Do not join samples further than GAP apart on the x-axis");
+        println!(
+            "      --x-format FMT  Format of the x-axis labels, valid options are 'number' or 'time'"
+        );
+        println!("      --precision N   Number of decimals in the axis labels");
+        println!("      --ymin   MIN    Set a fixed lower bound of the y-axis");
+        println!("      --ymax   MAX    Set a fixed upper bound of the y-axis");
+        println!("      --zero          Always include zero on the y-axis");
+        println!("      --sticky        Let the y-axis grow, but only shrink slowly");
+        println!("      --log    BASE   Use a logarithmic y-axis, valid options are '10' or '2'");
+        println!(
+            "      --sort   ORDER  Order of the bars of hbar, valid options are 'asc', 'desc' or 'label'"
+        );
+        println!(
+            "      --threshold V:C Draw a line at V, and color what is above it with C, such as 200:red"
+        );
+        println!(
+            "      --fill   FILL   Fill of area, valid options are 'block', 'braille' or a character"
+        );
+        println!(
+            "      --downsample M  How to combine samples, valid options are 'nearest', 'mean', 'minmax' or 'lttb'"
+        );
+        println!("      --spark-values  Write the min, max and last value after a spark graph");
+        println!("      --annotate      Write the min, max and last value next to the graph");
+        println!("      --stats         Write count, min, max, mean, stddev, percentiles and last value under the graph");
+        println!("      --title  TEXT   Write TEXT above the graph");
+        println!("      --x-label TEXT  Write TEXT under the x-axis");
+        println!("      --y-label TEXT  Write TEXT left of the y-axis");
+        println!("      --right  SERIES Draw SERIES, a number from 1 or a name, against a y-axis on the right");
+        println!("      --right-label TEXT Write TEXT right of the right y-axis");
+        println!("      --legend        Show the name of each series next to the graph");
+        println!("      --grid   LINES  Draw gridlines at the ticks, valid options are 'x', 'y' or 'both'");
+        println!("      --output FILE   Write the graph of the whole input to FILE as SVG");
+        println!("      --bins   N      Number of bins of hist");
+        println!("      --bin-width W   Width of the bins of hist");
         std::process::exit(1);
     };
 }
@@ -113,7 +267,7 @@ macro_rules! parseopts_panic {
 /// * `opts` - The opts struct to modify
 /// * `arg` - The name of the option/argument to read (without the -)
 /// * `value` - Optionally the value of the option/argument. This function will panic if not
-///             provided when it is required.
+///   provided when it is required.
 /// * `progname` - The first argument of the program, this is used for error messages.
 pub fn parseopt(opts: &mut OptsBuilder, arg: &str, value: Option<String>, progname: &str) {
     match arg {
@@ -122,6 +276,7 @@ pub fn parseopt(opts: &mut OptsBuilder, arg: &str, value: Option<String>, progna
                 println!("Missing value for {}\n", arg);
                 parseopts_panic!(progname);
             };
+            opts.mode = Mode::Graph;
             match graph_type.as_str() {
                 "star" => {
                     opts.graph_type = GraphType::Star;
@@ -135,9 +290,39 @@ pub fn parseopt(opts: &mut OptsBuilder, arg: &str, value: Option<String>, progna
                 "braille8" => {
                     opts.graph_type = GraphType::Braille(BrailleType::Dot8);
                 }
+                "half" => {
+                    opts.graph_type = GraphType::Blocks(BlockType::Half);
+                }
+                "quadrant" => {
+                    opts.graph_type = GraphType::Blocks(BlockType::Quadrant);
+                }
+                "bar" => {
+                    opts.graph_type = GraphType::Bar;
+                }
+                "hbar" => {
+                    opts.mode = Mode::HBar;
+                }
+                "hist" => {
+                    opts.mode = Mode::Histogram;
+                    opts.graph_type = GraphType::Bar;
+                }
+                "area" => {
+                    // Keep the fill if --fill came first
+                    if !matches!(opts.graph_type, GraphType::Area(_)) {
+                        opts.graph_type = GraphType::Area(AreaFill::Block);
+                    }
+                }
+                "spark" => {
+                    opts.graph_type = GraphType::Spark;
+                }
+                "scatter" => {
+                    // The first column is the x values of the points, as with --x-column
+                    opts.graph_type = GraphType::Scatter;
+                    opts.x_column = true;
+                }
                 t => {
                     println!(
-                        "Unknown type \"{}\", valid options are \"star\", \"ascii\" and \"braille\".\n",
+                        "Unknown type \"{}\", valid options are \"star\", \"ascii\", \"braille\", \"half\", \"quadrant\", \"bar\", \"area\", \"spark\", \"scatter\", \"hbar\" and \"hist\".\n",
                         t
                     );
                     parseopts_panic!(progname);
@@ -170,9 +355,11 @@ pub fn parseopt(opts: &mut OptsBuilder, arg: &str, value: Option<String>, progna
             opts.silent = true;
         }
         "a" | "ascii" => {
+            opts.mode = Mode::Graph;
             opts.graph_type = GraphType::Ascii;
         }
         "b" | "braille" => {
+            opts.mode = Mode::Graph;
             opts.graph_type = GraphType::Braille(BrailleType::Dot6);
         }
         "c" | "cut" => {
@@ -206,6 +393,275 @@ pub fn parseopt(opts: &mut OptsBuilder, arg: &str, value: Option<String>, progna
             };
             opts.width = Some(width);
         }
+        "x" | "x-column" => {
+            opts.x_column = true;
+        }
+        "max-gap" => {
+            let Some(max_gap) = value else {
+                println!("Missing value for {}\n", arg);
+                parseopts_panic!(progname);
+            };
+            let Ok(max_gap) = f64::from_str(&max_gap) else {
+                println!("Cannot parse number from \"{}\"\n", max_gap);
+                parseopts_panic!(progname);
+            };
+            opts.max_gap = Some(max_gap);
+        }
+        "x-format" => {
+            let Some(x_format) = value else {
+                println!("Missing value for {}\n", arg);
+                parseopts_panic!(progname);
+            };
+            opts.x_format = match x_format.as_str() {
+                "number" => XFormat::Number,
+                "time" => XFormat::Time,
+                t => {
+                    println!(
+                        "Unknown format \"{}\", valid options are \"number\", \"time\".\n",
+                        t
+                    );
+                    parseopts_panic!(progname);
+                }
+            }
+        }
+        "precision" => {
+            let Some(precision) = value else {
+                println!("Missing value for {}\n", arg);
+                parseopts_panic!(progname);
+            };
+            let Ok(precision) = usize::from_str(&precision) else {
+                println!("Cannot parse integer from \"{}\"\n", precision);
+                parseopts_panic!(progname);
+            };
+            opts.precision = precision;
+        }
+        "ymin" | "ymax" => {
+            let Some(bound) = value else {
+                println!("Missing value for {}\n", arg);
+                parseopts_panic!(progname);
+            };
+            let Ok(bound) = f64::from_str(&bound) else {
+                println!("Cannot parse number from \"{}\"\n", bound);
+                parseopts_panic!(progname);
+            };
+            if arg == "ymin" {
+                opts.y_min = Some(bound);
+            } else {
+                opts.y_max = Some(bound);
+            }
+            if let (Some(y_min), Some(y_max)) = (opts.y_min, opts.y_max) {
+                if y_min >= y_max {
+                    println!("--ymin must be smaller than --ymax\n");
+                    parseopts_panic!(progname);
+                }
+            }
+        }
+        "zero" => {
+            opts.include_zero = true;
+        }
+        "sticky" => {
+            opts.sticky = true;
+        }
+        "log" => {
+            let Some(base) = value else {
+                println!("Missing value for {}\n", arg);
+                parseopts_panic!(progname);
+            };
+            opts.y_scale = match base.as_str() {
+                "10" => YScale::Log10,
+                "2" => YScale::Log2,
+                t => {
+                    println!("Unknown base \"{}\", valid options are \"10\", \"2\".\n", t);
+                    parseopts_panic!(progname);
+                }
+            }
+        }
+        "sort" => {
+            let Some(sort) = value else {
+                println!("Missing value for {}\n", arg);
+                parseopts_panic!(progname);
+            };
+            opts.sort = match sort.as_str() {
+                "asc" => HBarSort::Ascending,
+                "desc" => HBarSort::Descending,
+                "label" => HBarSort::Label,
+                t => {
+                    println!(
+                        "Unknown order \"{}\", valid options are \"asc\", \"desc\", \"label\".\n",
+                        t
+                    );
+                    parseopts_panic!(progname);
+                }
+            }
+        }
+        "fill" => {
+            let Some(fill) = value else {
+                println!("Missing value for {}\n", arg);
+                parseopts_panic!(progname);
+            };
+            let mut chars = fill.chars();
+            let fill = match (fill.as_str(), chars.next(), chars.next()) {
+                ("block", _, _) => AreaFill::Block,
+                ("braille", _, _) => AreaFill::Braille,
+                (_, Some(c), None) => AreaFill::Glyph(c),
+                (t, _, _) => {
+                    println!(
+                        "Unknown fill \"{}\", valid options are \"block\", \"braille\" or a single character.\n",
+                        t
+                    );
+                    parseopts_panic!(progname);
+                }
+            };
+            opts.mode = Mode::Graph;
+            opts.graph_type = GraphType::Area(fill);
+        }
+        "threshold" => {
+            let Some(threshold) = value else {
+                println!("Missing value for {}\n", arg);
+                parseopts_panic!(progname);
+            };
+            let (value, color) = match threshold.split_once(':') {
+                Some((value, color)) => (value, color),
+                None => (threshold.as_str(), "red"),
+            };
+            let Ok(value) = f64::from_str(value) else {
+                println!("Cannot parse number from \"{}\"\n", value);
+                parseopts_panic!(progname);
+            };
+            let color = match color {
+                "red" => Color::Red,
+                "yellow" => Color::Yellow,
+                "green" => Color::Green,
+                "blue" => Color::Blue,
+                "magenta" => Color::Magenta,
+                "cyan" => Color::Cyan,
+                t => {
+                    println!(
+                        "Unknown color \"{}\", valid options are \"red\", \"yellow\", \"green\", \"blue\", \"magenta\", \"cyan\".\n",
+                        t
+                    );
+                    parseopts_panic!(progname);
+                }
+            };
+            opts.thresholds.push((value, color));
+        }
+        "downsample" => {
+            let Some(downsample) = value else {
+                println!("Missing value for {}\n", arg);
+                parseopts_panic!(progname);
+            };
+            opts.downsample = match downsample.as_str() {
+                "nearest" => Downsample::Nearest,
+                "mean" => Downsample::Mean,
+                "minmax" => Downsample::MinMax,
+                "lttb" => Downsample::Lttb,
+                t => {
+                    println!(
+                        "Unknown method \"{}\", valid options are \"nearest\", \"mean\", \"minmax\", \"lttb\".\n",
+                        t
+                    );
+                    parseopts_panic!(progname);
+                }
+            }
+        }
+        "spark-values" => {
+            opts.spark_values = true;
+        }
+        "annotate" => {
+            opts.annotate = true;
+        }
+        "stats" => {
+            opts.stats = true;
+        }
+        "title" => {
+            let Some(title) = value else {
+                println!("Missing value for {}\n", arg);
+                parseopts_panic!(progname);
+            };
+            opts.title = Some(title);
+        }
+        "x-label" => {
+            let Some(label) = value else {
+                println!("Missing value for {}\n", arg);
+                parseopts_panic!(progname);
+            };
+            opts.x_label = Some(label);
+        }
+        "y-label" => {
+            let Some(label) = value else {
+                println!("Missing value for {}\n", arg);
+                parseopts_panic!(progname);
+            };
+            opts.y_label = Some(label);
+        }
+        "right" => {
+            let Some(series) = value else {
+                println!("Missing value for {}\n", arg);
+                parseopts_panic!(progname);
+            };
+            opts.right.push(series);
+        }
+        "right-label" => {
+            let Some(label) = value else {
+                println!("Missing value for {}\n", arg);
+                parseopts_panic!(progname);
+            };
+            opts.right_label = Some(label);
+        }
+        "legend" => {
+            opts.legend = true;
+        }
+        "grid" => {
+            let Some(grid) = value else {
+                println!("Missing value for {}\n", arg);
+                parseopts_panic!(progname);
+            };
+            opts.grid = match grid.as_str() {
+                "x" => (false, true),
+                "y" => (true, false),
+                "both" => (true, true),
+                g => {
+                    println!(
+                        "Unknown gridlines \"{}\", valid options are \"x\", \"y\", \"both\".\n",
+                        g
+                    );
+                    parseopts_panic!(progname);
+                }
+            }
+        }
+        "output" => {
+            let Some(output) = value else {
+                println!("Missing value for {}\n", arg);
+                parseopts_panic!(progname);
+            };
+            opts.output = Some(output);
+        }
+        "bins" => {
+            let Some(bins) = value else {
+                println!("Missing value for {}\n", arg);
+                parseopts_panic!(progname);
+            };
+            let Ok(bins) = usize::from_str(&bins) else {
+                println!("Cannot parse integer from \"{}\"\n", bins);
+                parseopts_panic!(progname);
+            };
+            opts.binning = Binning::Count(bins);
+        }
+        "bin-width" => {
+            let Some(bin_width) = value else {
+                println!("Missing value for {}\n", arg);
+                parseopts_panic!(progname);
+            };
+            let Ok(bin_width) = f64::from_str(&bin_width) else {
+                println!("Cannot parse number from \"{}\"\n", bin_width);
+                parseopts_panic!(progname);
+            };
+            if bin_width <= 0.0 {
+                println!("--bin-width must be larger than zero\n");
+                parseopts_panic!(progname);
+            }
+            opts.binning = Binning::Width(bin_width);
+        }
         "help" => {
             parseopts_panic!(progname);
         }
@@ -231,6 +687,31 @@ pub fn parseopts() -> OptsBuilder {
         cut: false,
         in_file: None,
         color: None,
+        x_column: false,
+        max_gap: None,
+        x_format: XFormat::Number,
+        precision: 1,
+        y_min: None,
+        y_max: None,
+        include_zero: false,
+        sticky: false,
+        y_scale: YScale::Linear,
+        mode: Mode::Graph,
+        sort: HBarSort::Input,
+        binning: Binning::Auto,
+        spark_values: false,
+        downsample: Downsample::Nearest,
+        thresholds: Vec::new(),
+        annotate: false,
+        stats: false,
+        title: None,
+        x_label: None,
+        y_label: None,
+        right: Vec::new(),
+        right_label: None,
+        legend: false,
+        grid: (false, false),
+        output: None,
     };
 
     let mut it = std::env::args();
@@ -252,7 +733,10 @@ pub fn parseopts() -> OptsBuilder {
             } else {
                 arg_name = arg.clone();
                 match arg_name.as_str() {
-                    "width" | "height" | "last-n" | "color" => {
+                    "width" | "height" | "last-n" | "color" | "max-gap" | "x-format"
+                    | "precision" | "ymin" | "ymax" | "log" | "sort" | "bins" | "bin-width"
+                    | "downsample" | "fill" | "threshold" | "title" | "x-label" | "y-label"
+                    | "right" | "right-label" | "grid" | "output" => {
                         arg_value = it.next();
                     }
                     _ => (),
@@ -288,5 +772,5 @@ pub fn parseopts() -> OptsBuilder {
         }
     }
 
-    return opts;
+    opts
 }
@@ -0,0 +1,150 @@
+use crate::ticks::{format_si, half_span, lerp};
+
+/// Summary statistics of a series
+#[derive(PartialEq, Clone)]
+pub struct Stats {
+    /// Number of values, values that are not finite are not counted
+    pub count: usize,
+    /// Smallest value
+    pub min: f64,
+    /// Largest value
+    pub max: f64,
+    /// Mean of the values
+    pub mean: f64,
+    /// Population standard deviation of the values
+    pub stddev: f64,
+    /// Median of the values
+    pub p50: f64,
+    /// 95th percentile of the values
+    pub p95: f64,
+    /// 99th percentile of the values
+    pub p99: f64,
+    /// The last value of the series
+    pub last: f64,
+}
+
+impl Stats {
+    /// Compute the statistics of `values`, ignoring values that are not finite.
+    /// If there are no finite values, the count is 0 and every other field is NaN.
+    ///
+    /// # Arguments
+    ///
+    /// * `values` - The values of the series, in order
+    pub fn new(values: &[f64]) -> Self {
+        let mut sorted: Vec<f64> = values.iter().cloned().filter(|y| y.is_finite()).collect();
+        let last = sorted.last().cloned().unwrap_or(f64::NAN);
+        sorted.sort_by(f64::total_cmp);
+        let count = sorted.len();
+        let mut mean = sorted.iter().sum::<f64>() / count as f64;
+        if mean.is_infinite() {
+            // The sum of huge values overflows, the values are divided by the count first
+            mean = sorted.iter().map(|y| y / count as f64).sum();
+        }
+        let variance = sorted.iter().map(|y| (y - mean).powi(2)).sum::<f64>() / count as f64;
+        let mut stddev = variance.sqrt();
+        if stddev.is_infinite() {
+            // The squares overflow, the deviations are measured relative to the largest one
+            let scale = sorted
+                .iter()
+                .map(|y| half_span(mean, *y).abs())
+                .fold(0.0, f64::max);
+            let variance = sorted
+                .iter()
+                .map(|y| (half_span(mean, *y) / scale).powi(2))
+                .sum::<f64>()
+                / count as f64;
+            stddev = (variance.sqrt() * scale).min(f64::MAX / 2.0) * 2.0;
+        }
+        Stats {
+            count,
+            min: sorted.first().cloned().unwrap_or(f64::NAN),
+            max: sorted.last().cloned().unwrap_or(f64::NAN),
+            mean,
+            stddev,
+            p50: percentile(&sorted, 50.0),
+            p95: percentile(&sorted, 95.0),
+            p99: percentile(&sorted, 99.0),
+            last,
+        }
+    }
+
+    /// Format the statistics as a single line, like "n 60 min 5 max 55 ... last 16.4".
+    /// Fields are left out from the end if the line would be wider than `width`.
+    ///
+    /// # Arguments
+    ///
+    /// * `precision` - Maximum number of decimals of the values
+    /// * `width` - The widest the line may be
+    pub fn summary(&self, precision: usize, width: usize) -> String {
+        let fields = [
+            ("min", self.min),
+            ("max", self.max),
+            ("mean", self.mean),
+            ("sd", self.stddev),
+            ("p50", self.p50),
+            ("p95", self.p95),
+            ("p99", self.p99),
+            ("last", self.last),
+        ];
+        let mut line = format!("n {}", self.count);
+        if line.chars().count() > width {
+            return String::new();
+        }
+        for (name, value) in fields {
+            let field = format!(" {} {}", name, format_si(value, precision));
+            if line.chars().count() + field.chars().count() > width {
+                break;
+            }
+            line.push_str(&field);
+        }
+        line
+    }
+}
+
+/// The `p`th percentile of the sorted values, interpolated linearly between the closest values
+///
+/// # Arguments
+///
+/// * `sorted` - The values, sorted from smallest to largest
+/// * `p` - The percentile, from 0 to 100
+fn percentile(sorted: &[f64], p: f64) -> f64 {
+    if sorted.is_empty() {
+        return f64::NAN;
+    }
+    let rank = p / 100.0 * (sorted.len() - 1) as f64;
+    let below = rank.floor() as usize;
+    let above = rank.ceil() as usize;
+    lerp(sorted[below], sorted[above], rank - below as f64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn percentile_interpolates() {
+        let sorted = [1.0, 2.0, 3.0, 4.0];
+        assert_eq!(percentile(&sorted, 0.0), 1.0);
+        assert_eq!(percentile(&sorted, 50.0), 2.5);
+        assert_eq!(percentile(&sorted, 100.0), 4.0);
+    }
+
+    #[test]
+    fn stats_ignore_non_finite_values() {
+        let stats = Stats::new(&[f64::NAN, 2.0, f64::INFINITY, 4.0]);
+        assert_eq!(stats.count, 2);
+        assert_eq!((stats.min, stats.max, stats.mean), (2.0, 4.0, 3.0));
+        assert_eq!((stats.stddev, stats.p50, stats.last), (1.0, 3.0, 4.0));
+    }
+
+    #[test]
+    fn summary_leaves_out_fields_that_do_not_fit() {
+        let stats = Stats::new(&[1.0, 2.0, 3.0]);
+        assert_eq!(
+            stats.summary(1, 80),
+            "n 3 min 1 max 3 mean 2 sd 816.5m p50 2 p95 2.9 p99 3 last 3"
+        );
+        assert_eq!(stats.summary(1, 20), "n 3 min 1 max 3");
+        assert_eq!(stats.summary(1, 2), "");
+    }
+}
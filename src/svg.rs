@@ -0,0 +1,501 @@
+use crate::canvas::{Canvas, GraphPixel, Pixel};
+use std::fmt::Write;
+
+/// Width of a cell of the canvas in the SVG, in pixels
+pub const CELL_WIDTH: f64 = 10.0;
+/// Height of a cell of the canvas in the SVG, in pixels
+pub const CELL_HEIGHT: f64 = 20.0;
+/// Size of the font text is written with, in pixels
+const FONT_SIZE: f64 = 16.0;
+/// Distance from the top of a cell to the baseline of its text, in pixels
+const BASELINE: f64 = 15.0;
+/// Opacity of things drawn with GraphPixel::Dim, such as gridlines
+const DIM_OPACITY: f64 = 0.35;
+
+/// A SVG image, with shapes in the same coordinates as the cells of a canvas.
+/// The cell (x, y) covers x * CELL_WIDTH to (x + 1) * CELL_WIDTH horizontally,
+/// and y * CELL_HEIGHT to (y + 1) * CELL_HEIGHT vertically.
+pub struct Svg {
+    /// Width of the image, in pixels
+    width: f64,
+    /// Height of the image, in pixels
+    height: f64,
+    /// The elements of the image, in the order they are drawn
+    body: String,
+    /// Number of clip paths, used to give each one its own id
+    clips: usize,
+}
+
+impl Svg {
+    /// Create an empty image with a white background
+    ///
+    /// # Arguments
+    ///
+    /// * `columns` - Width of the image, in cells
+    /// * `rows` - Height of the image, in cells
+    pub fn new(columns: usize, rows: usize) -> Self {
+        Svg {
+            width: columns as f64 * CELL_WIDTH,
+            height: rows as f64 * CELL_HEIGHT,
+            body: String::new(),
+            clips: 0,
+        }
+    }
+
+    /// Draw the cells of a canvas. Text is written as text, box drawing characters are
+    /// drawn as lines, and braille and block characters as dots and rectangles.
+    ///
+    /// # Arguments
+    ///
+    /// * `canvas` - The canvas to draw
+    /// * `skip` - Cells for which this returns true are left out, given (x, y) of the cell
+    pub fn cells<F>(&mut self, canvas: &Canvas, skip: F)
+    where
+        F: Fn(usize, usize) -> bool,
+    {
+        // The box drawing characters are joined into long lines once all cells are known
+        let mut boxes = vec![None; canvas.width() * canvas.height()];
+        for y in 0..canvas.height() {
+            // Neighbouring text of the same color is written as one text element
+            let mut run: Option<(usize, String, &GraphPixel<Pixel>)> = None;
+            for x in 0..canvas.width() {
+                let px = match canvas.get(x, y) {
+                    Some(px) if !skip(x, y) => px,
+                    _ => &GraphPixel::Blank,
+                };
+                let text = match px.inner() {
+                    Some(Pixel::Char(c)) if box_lines(*c).is_none() => Some(c.to_string()),
+                    Some(Pixel::Text(text)) => Some(text.clone()),
+                    _ => None,
+                };
+                match (&mut run, text) {
+                    (Some((_, run_text, run_px)), Some(text)) if same_color(run_px, px) => {
+                        run_text.push_str(&text);
+                        continue;
+                    }
+                    (_, text) => {
+                        if let Some((start, run_text, run_px)) = run.take() {
+                            self.text_run(start, y, x - start, &run_text, run_px);
+                        }
+                        run = text.map(|text| (x, text, px));
+                    }
+                }
+                match px.inner() {
+                    Some(Pixel::Char(c)) => {
+                        boxes[y * canvas.width() + x] =
+                            box_lines(*c).map(|lines| (lines, color(px), opacity(px)));
+                    }
+                    Some(Pixel::Braille(bits)) => self.braille(x, y, *bits, px),
+                    Some(Pixel::Block(bits)) => self.block(x, y, *bits, px),
+                    _ => (),
+                }
+            }
+            if let Some((start, run_text, run_px)) = run {
+                self.text_run(start, y, canvas.width() - start, &run_text, run_px);
+            }
+        }
+        self.box_drawing(&boxes, canvas.width(), canvas.height());
+    }
+
+    /// Draw a line through points, in pixels
+    ///
+    /// # Arguments
+    ///
+    /// * `points` - The (x, y) points of the line
+    /// * `color` - Color of the line, see color
+    pub fn polyline(&mut self, points: &[(f64, f64)], color: &str) {
+        writeln!(
+            self.body,
+            r#"<polyline points="{}" fill="none" stroke="{}" stroke-width="1.5" stroke-linejoin="round"/>"#,
+            point_list(points),
+            color
+        )
+        .unwrap();
+    }
+
+    /// Draw a filled shape with corners at points, in pixels
+    ///
+    /// # Arguments
+    ///
+    /// * `points` - The (x, y) corners of the shape
+    /// * `color` - Color of the shape, see color
+    /// * `opacity` - Opacity of the fill, from 0 to 1
+    pub fn polygon(&mut self, points: &[(f64, f64)], color: &str, opacity: f64) {
+        writeln!(
+            self.body,
+            r#"<polygon points="{}" fill="{}" fill-opacity="{}"/>"#,
+            point_list(points),
+            color,
+            opacity
+        )
+        .unwrap();
+    }
+
+    /// Draw a filled rectangle, in pixels
+    ///
+    /// # Arguments
+    ///
+    /// * `x` - Left side of the rectangle
+    /// * `y` - Top side of the rectangle
+    /// * `width` - Width of the rectangle
+    /// * `height` - Height of the rectangle
+    /// * `color` - Color of the rectangle, see color
+    pub fn rect(&mut self, x: f64, y: f64, width: f64, height: f64, color: &str) {
+        writeln!(
+            self.body,
+            r#"<rect x="{:.1}" y="{:.1}" width="{:.1}" height="{:.1}" fill="{}"/>"#,
+            x, y, width, height, color
+        )
+        .unwrap();
+    }
+
+    /// Draw a filled circle, in pixels
+    ///
+    /// # Arguments
+    ///
+    /// * `x` - Horizontal position of the center
+    /// * `y` - Vertical position of the center
+    /// * `radius` - Radius of the circle
+    /// * `color` - Color of the circle, see color
+    pub fn circle(&mut self, x: f64, y: f64, radius: f64, color: &str) {
+        writeln!(
+            self.body,
+            r#"<circle cx="{:.1}" cy="{:.1}" r="{:.1}" fill="{}"/>"#,
+            x, y, radius, color
+        )
+        .unwrap();
+    }
+
+    /// Draw a straight line, in pixels
+    ///
+    /// # Arguments
+    ///
+    /// * `from` - (x, y) position of the start of the line
+    /// * `to` - (x, y) position of the end of the line
+    /// * `color` - Color of the line, see color
+    pub fn line(&mut self, from: (f64, f64), to: (f64, f64), color: &str) {
+        writeln!(
+            self.body,
+            r#"<line x1="{:.1}" y1="{:.1}" x2="{:.1}" y2="{:.1}" stroke="{}"/>"#,
+            from.0, from.1, to.0, to.1, color
+        )
+        .unwrap();
+    }
+
+    /// Only show what is drawn until end_clip inside some cells of the canvas
+    ///
+    /// # Arguments
+    ///
+    /// * `cells` - The (x, y) cells to show, sorted by row and then by column
+    pub fn clip(&mut self, cells: &[(usize, usize)]) {
+        self.clips += 1;
+        writeln!(self.body, r#"<clipPath id="clip{}">"#, self.clips).unwrap();
+        // Neighbouring cells of a row are joined into one rectangle
+        let mut start = 0;
+        for i in 1..=cells.len() {
+            let (x, y) = cells[i - 1];
+            if cells.get(i) == Some(&(x + 1, y)) {
+                continue;
+            }
+            writeln!(
+                self.body,
+                r#"<rect x="{:.1}" y="{:.1}" width="{:.1}" height="{:.1}"/>"#,
+                cells[start].0 as f64 * CELL_WIDTH,
+                y as f64 * CELL_HEIGHT,
+                (x + 1 - cells[start].0) as f64 * CELL_WIDTH,
+                CELL_HEIGHT
+            )
+            .unwrap();
+            start = i;
+        }
+        writeln!(self.body, "</clipPath>").unwrap();
+        writeln!(self.body, r#"<g clip-path="url(#clip{})">"#, self.clips).unwrap();
+    }
+
+    /// Stop clipping what is drawn, see clip
+    pub fn end_clip(&mut self) {
+        writeln!(self.body, "</g>").unwrap();
+    }
+
+    /// Write text covering `columns` cells, starting at the cell (x, y)
+    fn text_run(&mut self, x: usize, y: usize, columns: usize, text: &str, px: &GraphPixel<Pixel>) {
+        if text.trim().is_empty() {
+            return;
+        }
+        writeln!(
+            self.body,
+            r#"<text x="{:.1}" y="{:.1}" textLength="{:.1}" lengthAdjust="spacingAndGlyphs" fill="{}"{}>{}</text>"#,
+            x as f64 * CELL_WIDTH,
+            y as f64 * CELL_HEIGHT + BASELINE,
+            columns as f64 * CELL_WIDTH,
+            color(px),
+            opacity(px),
+            escape(text)
+        )
+        .unwrap();
+    }
+
+    /// Draw box drawing characters as lines through the centers of the cells.
+    /// Each character has lines from the center of its cell to some of its sides, lines of
+    /// neighbouring cells that continue each other are drawn as a single line.
+    ///
+    /// # Arguments
+    ///
+    /// * `boxes` - The lines, color and opacity of each cell, row by row
+    /// * `columns` - Number of columns of cells
+    /// * `rows` - Number of rows of cells
+    fn box_drawing(&mut self, boxes: &[Option<BoxCell>], columns: usize, rows: usize) {
+        // Each cell has two halves in both directions, the half away from the center
+        // is drawn if the line goes to that side
+        let horizontal = |x: usize, y: usize, half: usize| {
+            boxes[y * columns + x]
+                .clone()
+                .filter(|(lines, _, _)| if half == 0 { lines.left } else { lines.right })
+        };
+        let vertical = |x: usize, y: usize, half: usize| {
+            boxes[y * columns + x]
+                .clone()
+                .filter(|(lines, _, _)| if half == 0 { lines.up } else { lines.down })
+        };
+        for y in 0..rows {
+            let center = (y as f64 + 0.5) * CELL_HEIGHT;
+            let halves: Vec<Option<BoxCell>> = (0..columns * 2)
+                .map(|h| horizontal(h / 2, y, h % 2))
+                .collect();
+            for (start, end, style) in runs(&halves) {
+                let (x1, x2) = (
+                    start as f64 * CELL_WIDTH / 2.0,
+                    end as f64 * CELL_WIDTH / 2.0,
+                );
+                self.box_line((x1, center), (x2, center), &style);
+            }
+        }
+        for x in 0..columns {
+            let center = (x as f64 + 0.5) * CELL_WIDTH;
+            let halves: Vec<Option<BoxCell>> =
+                (0..rows * 2).map(|h| vertical(x, h / 2, h % 2)).collect();
+            for (start, end, style) in runs(&halves) {
+                let (y1, y2) = (
+                    start as f64 * CELL_HEIGHT / 2.0,
+                    end as f64 * CELL_HEIGHT / 2.0,
+                );
+                self.box_line((center, y1), (center, y2), &style);
+            }
+        }
+    }
+
+    /// Draw a line of box drawing characters, in pixels
+    fn box_line(&mut self, from: (f64, f64), to: (f64, f64), (lines, color, opacity): &BoxCell) {
+        let dash = if lines.dashed {
+            r#" stroke-dasharray="3 2""#
+        } else {
+            ""
+        };
+        writeln!(
+            self.body,
+            r#"<line x1="{:.1}" y1="{:.1}" x2="{:.1}" y2="{:.1}" stroke="{}"{}{}/>"#,
+            from.0, from.1, to.0, to.1, color, dash, opacity
+        )
+        .unwrap();
+    }
+
+    /// Draw the dots of a braille character, see canvas::brc for the order of the bits
+    fn braille(&mut self, x: usize, y: usize, bits: u32, px: &GraphPixel<Pixel>) {
+        // The column and row inside the cell of each bit
+        const DOTS: [(usize, usize); 8] = [
+            (0, 0),
+            (0, 1),
+            (0, 2),
+            (1, 0),
+            (1, 1),
+            (1, 2),
+            (0, 3),
+            (1, 3),
+        ];
+        for (i, (column, row)) in DOTS.iter().enumerate() {
+            if bits & (1 << i) != 0 {
+                self.circle(
+                    (x as f64 + (*column as f64 + 0.5) / 2.0) * CELL_WIDTH,
+                    (y as f64 + (*row as f64 + 0.5) / 4.0) * CELL_HEIGHT,
+                    CELL_WIDTH / 6.0,
+                    color(px),
+                );
+            }
+        }
+    }
+
+    /// Draw the quadrants of a block character, see canvas::blc for the order of the bits
+    fn block(&mut self, x: usize, y: usize, bits: u32, px: &GraphPixel<Pixel>) {
+        for i in 0..4 {
+            if bits & (1 << i) != 0 {
+                let (column, row) = (i / 2, i % 2);
+                self.rect(
+                    (x as f64 + column as f64 / 2.0) * CELL_WIDTH,
+                    (y as f64 + row as f64 / 2.0) * CELL_HEIGHT,
+                    CELL_WIDTH / 2.0,
+                    CELL_HEIGHT / 2.0,
+                    color(px),
+                );
+            }
+        }
+    }
+}
+
+impl std::fmt::Display for Svg {
+    /// Turn the image into a SVG document
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        writeln!(
+            f,
+            r#"<svg xmlns="http://www.w3.org/2000/svg" width="{}" height="{}" viewBox="0 0 {} {}" font-family="monospace" font-size="{}" xml:space="preserve">"#,
+            self.width, self.height, self.width, self.height, FONT_SIZE
+        )?;
+        writeln!(f, r#"<rect width="100%" height="100%" fill="white"/>"#)?;
+        write!(f, "{}", self.body)?;
+        write!(f, "</svg>")
+    }
+}
+
+/// The lines of a box drawing character, the color and the opacity attribute it is drawn with
+type BoxCell = (BoxLines, &'static str, String);
+
+/// The sides of a cell a box drawing character has lines to
+#[derive(Clone, Copy)]
+struct BoxLines {
+    up: bool,
+    down: bool,
+    left: bool,
+    right: bool,
+    dashed: bool,
+}
+
+/// The lines of a box drawing character, or None for any other character
+fn box_lines(c: char) -> Option<BoxLines> {
+    let (up, down, left, right) = match c {
+        '─' | '┄' => (false, false, true, true),
+        '│' => (true, true, false, false),
+        '┌' | '╭' => (false, true, false, true),
+        '┐' | '╮' => (false, true, true, false),
+        '└' | '╰' => (true, false, false, true),
+        '┘' | '╯' => (true, false, true, false),
+        '├' => (true, true, false, true),
+        '┤' => (true, true, true, false),
+        '┬' => (false, true, true, true),
+        '┴' => (true, false, true, true),
+        '┼' => (true, true, true, true),
+        _ => return None,
+    };
+    Some(BoxLines {
+        up,
+        down,
+        left,
+        right,
+        dashed: c == '┄',
+    })
+}
+
+/// Find the runs of neighbouring halves of cells that are drawn the same way,
+/// returns the first half, the half after the last and how the run is drawn
+fn runs(halves: &[Option<BoxCell>]) -> Vec<(usize, usize, BoxCell)> {
+    let mut runs: Vec<(usize, usize, BoxCell)> = Vec::new();
+    for (h, half) in halves.iter().enumerate() {
+        let Some(style) = half else {
+            continue;
+        };
+        match runs.last_mut() {
+            Some((_, end, (lines, color, opacity)))
+                if *end == h
+                    && lines.dashed == style.0.dashed
+                    && *color == style.1
+                    && *opacity == style.2 =>
+            {
+                *end = h + 1;
+            }
+            _ => runs.push((h, h + 1, style.clone())),
+        }
+    }
+    runs
+}
+
+/// The color a pixel is drawn with in a SVG
+///
+/// # Arguments
+///
+/// * `px` - The pixel, only its variant is used
+pub fn color<T>(px: &GraphPixel<T>) -> &'static str {
+    match px {
+        GraphPixel::Green(_) => "#2ca02c",
+        GraphPixel::Blue(_) => "#1f77b4",
+        GraphPixel::Red(_) => "#d62728",
+        GraphPixel::Yellow(_) => "#d4a017",
+        GraphPixel::Magenta(_) => "#c2188b",
+        GraphPixel::Cyan(_) => "#17a2b8",
+        GraphPixel::Normal(_) | GraphPixel::Dim(_) | GraphPixel::Blank => "black",
+    }
+}
+
+/// The opacity attribute of a pixel, if it is not fully opaque
+fn opacity<T>(px: &GraphPixel<T>) -> String {
+    match px {
+        GraphPixel::Dim(_) => format!(r#" opacity="{}""#, DIM_OPACITY),
+        _ => String::new(),
+    }
+}
+
+/// Whether two pixels are drawn the same way, apart from their content
+fn same_color<T>(a: &GraphPixel<T>, b: &GraphPixel<T>) -> bool {
+    std::mem::discriminant(a) == std::mem::discriminant(b)
+}
+
+/// Format points as the value of a points attribute
+fn point_list(points: &[(f64, f64)]) -> String {
+    points
+        .iter()
+        .map(|(x, y)| format!("{:.1},{:.1}", x, y))
+        .collect::<Vec<String>>()
+        .join(" ")
+}
+
+/// Escape the characters that have a meaning in XML
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn box_drawing_is_joined_into_lines() {
+        let mut canvas = Canvas::new(4, 2);
+        canvas.rect(0, 0, 4, 2, GraphPixel::Normal);
+        let mut svg = Svg::new(4, 2);
+        svg.cells(&canvas, |_, _| false);
+        let lines: Vec<String> = svg
+            .to_string()
+            .lines()
+            .filter(|line| line.starts_with("<line"))
+            .map(|line| line.to_string())
+            .collect();
+        assert_eq!(
+            lines,
+            [
+                r#"<line x1="5.0" y1="10.0" x2="35.0" y2="10.0" stroke="black"/>"#,
+                r#"<line x1="5.0" y1="30.0" x2="35.0" y2="30.0" stroke="black"/>"#,
+                r#"<line x1="5.0" y1="10.0" x2="5.0" y2="30.0" stroke="black"/>"#,
+                r#"<line x1="35.0" y1="10.0" x2="35.0" y2="30.0" stroke="black"/>"#,
+            ]
+        );
+    }
+
+    #[test]
+    fn text_is_escaped() {
+        let mut canvas = Canvas::new(5, 1);
+        canvas.text(0, 0, "a<b&c", GraphPixel::Normal);
+        let mut svg = Svg::new(5, 1);
+        svg.cells(&canvas, |_, _| false);
+        assert!(svg.to_string().contains(">a&lt;b&amp;c</text>"));
+    }
+}
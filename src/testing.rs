@@ -0,0 +1,12 @@
+/// Whether two lists of values are the same, counting NaN as equal to NaN
+///
+/// # Arguments
+///
+/// * `a` - The values found
+/// * `b` - The values expected
+pub fn same(a: &[f64], b: &[f64]) -> bool {
+    a.len() == b.len()
+        && a.iter()
+            .zip(b)
+            .all(|(a, b)| a == b || (a.is_nan() && b.is_nan()))
+}
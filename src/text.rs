@@ -0,0 +1,117 @@
+/// Number of terminal columns a character takes up,
+/// 2 for wide characters such as CJK ideographs, 0 for combining marks and control characters
+///
+/// # Arguments
+///
+/// * `c` - The character to measure
+pub fn char_width(c: char) -> usize {
+    match c as u32 {
+        0x00..=0x1F | 0x7F..=0x9F => 0,
+        0x0300..=0x036F | 0x1AB0..=0x1AFF | 0x1DC0..=0x1DFF | 0x20D0..=0x20FF => 0,
+        0x200B..=0x200F | 0xFE00..=0xFE0F | 0xFE20..=0xFE2F => 0,
+        0x1100..=0x115F
+        | 0x2E80..=0x303E
+        | 0x3041..=0x33FF
+        | 0x3400..=0x4DBF
+        | 0x4E00..=0x9FFF
+        | 0xA000..=0xA4CF
+        | 0xAC00..=0xD7A3
+        | 0xF900..=0xFAFF
+        | 0xFE30..=0xFE4F
+        | 0xFF00..=0xFF60
+        | 0xFFE0..=0xFFE6
+        | 0x1F300..=0x1F64F
+        | 0x1F900..=0x1F9FF
+        | 0x20000..=0x3FFFD => 2,
+        _ => 1,
+    }
+}
+
+/// Number of terminal columns a text takes up, see char_width
+///
+/// # Arguments
+///
+/// * `text` - The text to measure
+pub fn display_width(text: &str) -> usize {
+    text.chars().map(char_width).sum()
+}
+
+/// Shorten a text to at most `width` columns, marking that it was shortened with …
+///
+/// # Arguments
+///
+/// * `text` - The text to shorten
+/// * `width` - Maximum number of columns
+pub fn truncate(text: &str, width: usize) -> String {
+    if display_width(text) <= width {
+        return text.to_string();
+    }
+    if width == 0 {
+        return String::new();
+    }
+    let mut truncated = String::new();
+    let mut used = 0;
+    for c in text.chars() {
+        if used + char_width(c) > width - 1 {
+            break;
+        }
+        used += char_width(c);
+        truncated.push(c);
+    }
+    truncated.push('…');
+    truncated
+}
+
+/// Split a text into the contents of each column it takes up.
+/// Combining marks are kept with the character before them, and a wide character
+/// is followed by an empty string for the second column it covers.
+///
+/// # Arguments
+///
+/// * `text` - The text to split
+pub fn cells(text: &str) -> Vec<String> {
+    let mut cells: Vec<String> = Vec::new();
+    for c in text.chars().filter(|c| !c.is_control()) {
+        match char_width(c) {
+            0 => {
+                // Find the character the mark belongs to, not the empty second half of it
+                if let Some(cell) = cells.iter_mut().rev().find(|cell| !cell.is_empty()) {
+                    cell.push(c);
+                }
+            }
+            2 => {
+                cells.push(c.to_string());
+                cells.push(String::new());
+            }
+            _ => cells.push(c.to_string()),
+        }
+    }
+    cells
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn wide_characters_and_marks() {
+        assert_eq!(char_width('a'), 1);
+        assert_eq!(char_width('日'), 2);
+        assert_eq!(char_width('\u{301}'), 0);
+        assert_eq!(display_width("日本 e\u{301}"), 6);
+    }
+
+    #[test]
+    fn truncate_marks_what_was_left_out() {
+        assert_eq!(truncate("label", 5), "label");
+        assert_eq!(truncate("labels", 5), "labe…");
+        // A wide character that does not fit is left out as a whole
+        assert_eq!(truncate("日本語", 4), "日…");
+        assert_eq!(truncate("label", 0), "");
+    }
+
+    #[test]
+    fn cells_of_wide_characters_and_marks() {
+        assert_eq!(cells("a日e\u{301}"), ["a", "日", "", "e\u{301}"]);
+    }
+}
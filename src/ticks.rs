@@ -0,0 +1,516 @@
+/// Suffixes used by format_si, from 10^-12 to 10^18
+const SI_SUFFIXES: [&str; 11] = ["p", "n", "µ", "m", "", "k", "M", "G", "T", "P", "E"];
+/// Index of the empty suffix in SI_SUFFIXES
+const SI_UNIT_INDEX: i32 = 4;
+
+/// Find a "nice" step between ticks, that is 1, 2 or 5 times a power of ten,
+/// so that there are at most `max_count` ticks in `range`.
+///
+/// # Arguments
+///
+/// * `range` - The distance between the first and last value of the axis
+/// * `max_count` - The maximum number of ticks wanted
+pub fn nice_step(range: f64, max_count: usize) -> f64 {
+    let raw_step = range / max_count.max(1) as f64;
+    let magnitude = 10f64.powf(raw_step.log10().floor());
+    let normalized = raw_step / magnitude;
+    let nice = if normalized <= 1.0 {
+        1.0
+    } else if normalized <= 2.0 {
+        2.0
+    } else if normalized <= 5.0 {
+        5.0
+    } else {
+        10.0
+    };
+    nice * magnitude
+}
+
+/// Half of the distance from `a` to `b`.
+/// Unlike the distance itself, this does not overflow for values near the largest f64.
+///
+/// # Arguments
+///
+/// * `a` - The value to measure from
+/// * `b` - The value to measure to
+pub fn half_span(a: f64, b: f64) -> f64 {
+    b / 2.0 - a / 2.0
+}
+
+/// Where `value` is between `min` and `max`, 0 at `min` and 1 at `max`, see half_span
+///
+/// # Arguments
+///
+/// * `value` - The value to place
+/// * `min` - The value at 0
+/// * `max` - The value at 1
+pub fn fraction(value: f64, min: f64, max: f64) -> f64 {
+    half_span(min, value) / half_span(min, max)
+}
+
+/// The value `t` of the way from `a` to `b`, the inverse of fraction
+///
+/// # Arguments
+///
+/// * `a` - The value at 0
+/// * `b` - The value at 1
+/// * `t` - How far to go from `a` to `b`
+pub fn lerp(a: f64, b: f64, t: f64) -> f64 {
+    let half = half_span(a, b) * t;
+    if (half * 2.0).is_finite() {
+        a + half * 2.0
+    } else {
+        a + half + half
+    }
+}
+
+/// Find the multiples of a nice step between `min` and `max`, see nice_step.
+/// If the range is empty, `min` is the only tick.
+///
+/// # Arguments
+///
+/// * `min` - Smallest value of the axis
+/// * `max` - Largest value of the axis
+/// * `max_count` - The maximum number of ticks wanted
+pub fn nice_ticks(min: f64, max: f64, max_count: usize) -> Vec<f64> {
+    if !min.is_finite() || !max.is_finite() {
+        return Vec::new();
+    }
+    if max <= min {
+        return vec![min];
+    }
+    let mut step = if (max - min).is_finite() {
+        nice_step(max - min, max_count)
+    } else {
+        nice_step(half_span(min, max), max_count.div_ceil(2))
+    };
+    let mut ticks = multiples(min, max, step);
+    // Rounding the step up to a nice value can still leave one tick too many
+    while ticks.len() > max_count.max(1) {
+        step = nice_step(step * 1.5, 1);
+        ticks = multiples(min, max, step);
+    }
+    ticks
+}
+
+/// Find nice ticks for unix timestamps between `min` and `max`, the steps are whole seconds,
+/// minutes, hours, days or weeks, instead of powers of ten.
+/// Longer ranges are split at the first day of a month or of a year.
+///
+/// # Arguments
+///
+/// * `min` - Smallest timestamp of the axis
+/// * `max` - Largest timestamp of the axis
+/// * `max_count` - The maximum number of ticks wanted
+pub fn time_ticks(min: f64, max: f64, max_count: usize) -> Vec<f64> {
+    const STEPS: [f64; 20] = [
+        1.0, 2.0, 5.0, 10.0, 15.0, 30.0, // seconds
+        60.0, 120.0, 300.0, 600.0, 900.0, 1800.0, // minutes
+        3600.0, 7200.0, 10800.0, 21600.0, 43200.0, // hours
+        86400.0, 172800.0, 604800.0, // days
+    ];
+    const MONTH_STEPS: [i64; 4] = [1, 2, 3, 6];
+    if !min.is_finite() || !max.is_finite() {
+        return Vec::new();
+    }
+    if max <= min {
+        return vec![min];
+    }
+    let max_count = max_count.max(1);
+    // A step with fewer than max_count + 1 steps in the range has at most max_count + 1
+    // multiples in it, so only the steps that can fit are tried
+    let fits = |steps: f64| steps < (max_count + 1) as f64;
+    for step in STEPS {
+        if fits((max - min) / step) {
+            let ticks = multiples(min, max, step);
+            if ticks.len() <= max_count {
+                return ticks;
+            }
+        }
+    }
+
+    // Calendar dates are only worked out for timestamps within about 30 million years
+    if min < -1e15 || max > 1e15 {
+        return nice_ticks(min, max, max_count);
+    }
+    // Months have an average length of 30.436875 days in the gregorian calendar
+    let months = (max - min) / (30.436875 * 86400.0);
+    for step in MONTH_STEPS {
+        if fits(months / step as f64) {
+            let ticks = month_ticks(min, max, step);
+            if ticks.len() <= max_count {
+                return ticks;
+            }
+        }
+    }
+    let mut years = nice_step(months / 12.0, max_count).max(1.0);
+    loop {
+        let ticks = month_ticks(min, max, (years * 12.0) as i64);
+        if ticks.len() <= max_count {
+            return ticks;
+        }
+        years = nice_step(years * 1.5, 1);
+    }
+}
+
+/// The first days of the months between `min` and `max` that are a multiple of
+/// `step` months after year 0, as unix timestamps. Steps of 12 months or more give
+/// the first days of years, that are a multiple of `step / 12`.
+///
+/// # Arguments
+///
+/// * `min` - Smallest timestamp
+/// * `max` - Largest timestamp
+/// * `step` - Number of months between the ticks
+fn month_ticks(min: f64, max: f64, step: i64) -> Vec<f64> {
+    let month_of = |timestamp: f64| {
+        let (year, month, _) = civil_from_days((timestamp / 86400.0).floor() as i64);
+        year * 12 + month - 1
+    };
+    let step = step.max(1);
+    let mut month = month_of(min).div_euclid(step) * step;
+    let mut ticks = Vec::new();
+    while month <= month_of(max) {
+        let days = days_from_civil(month.div_euclid(12), month.rem_euclid(12) + 1, 1);
+        let tick = days as f64 * 86400.0;
+        if tick >= min && tick <= max {
+            ticks.push(tick);
+        }
+        month += step;
+    }
+    ticks
+}
+
+/// Find ticks for a logarithmic axis, between `base^min_exponent` and `base^max_exponent`.
+/// The ticks are powers of `base` if the axis covers at least two of them,
+/// otherwise nice values are used, as with nice_ticks.
+/// The ticks are returned as values, not exponents.
+///
+/// # Arguments
+///
+/// * `min_exponent` - Exponent of the smallest value of the axis
+/// * `max_exponent` - Exponent of the largest value of the axis
+/// * `base` - Base of the logarithm
+/// * `max_count` - The maximum number of ticks wanted
+pub fn log_ticks(min_exponent: f64, max_exponent: f64, base: f64, max_count: usize) -> Vec<f64> {
+    if !min_exponent.is_finite() || !max_exponent.is_finite() {
+        return Vec::new();
+    }
+    // The logarithm of a power of the base can be off by a rounding error,
+    // such as 2.9999999999999996 for 1000, which should still get its tick
+    let first = (min_exponent - 1e-9).ceil() as i32;
+    let last = (max_exponent + 1e-9).floor() as i32;
+    if last > first {
+        // Skip some of the powers if there are too many of them
+        let count = (last - first + 1) as usize;
+        let every = count.div_ceil(max_count.max(1));
+        return (first..=last)
+            .step_by(every)
+            .map(|exponent| base.powi(exponent))
+            .collect();
+    }
+    nice_ticks(
+        base.powf(min_exponent),
+        base.powf(max_exponent),
+        max_count.min(5),
+    )
+}
+
+/// All multiples of `step` between `min` and `max`
+fn multiples(min: f64, max: f64, step: f64) -> Vec<f64> {
+    if step.is_nan() || step <= 0.0 {
+        return vec![min];
+    }
+    let first = (min / step).ceil() as i64;
+    let last = (max / step).floor() as i64;
+    (first..=last)
+        .map(|i| {
+            let tick = i as f64 * step;
+            // Avoid writing -0
+            if tick == 0.0 {
+                0.0
+            } else {
+                tick
+            }
+        })
+        .collect()
+}
+
+/// Index into SI_SUFFIXES for the suffix `value` should be written with,
+/// this is outside of SI_SUFFIXES if the value is too large or too small for the suffixes
+fn si_index(value: f64) -> i32 {
+    if value == 0.0 || !value.is_finite() {
+        return SI_UNIT_INDEX;
+    }
+    let exponent = (value.abs().log10() / 3.0).floor() as i32;
+    exponent + SI_UNIT_INDEX
+}
+
+/// Whether there is a suffix for the index from si_index
+fn has_suffix(index: i32) -> bool {
+    (0..SI_SUFFIXES.len() as i32).contains(&index)
+}
+
+/// Remove trailing zeros of the decimals of a formatted number, and the point if nothing is left
+fn trim_decimals(formatted: &str) -> &str {
+    if formatted.contains('.') {
+        formatted.trim_end_matches('0').trim_end_matches('.')
+    } else {
+        formatted
+    }
+}
+
+/// Format a number with a SI suffix (k, M, µ, ...), so the number in front of the suffix is
+/// between 1 and 1000. Trailing zeros of the decimals are removed.
+/// Numbers too large or too small for the suffixes are written in scientific notation,
+/// such as 1.5e300.
+///
+/// # Arguments
+///
+/// * `value` - The value to format
+/// * `precision` - Maximum number of decimals
+pub fn format_si(value: f64, precision: usize) -> String {
+    if !value.is_finite() {
+        return format!("{}", value);
+    }
+    let mut index = si_index(value);
+    if !has_suffix(index) {
+        return format_scientific(value, precision);
+    }
+    let mut mantissa = value / 1000f64.powi(index - SI_UNIT_INDEX);
+    let mut formatted = format!("{:.*}", precision, mantissa);
+    // Rounding can make the number reach the next suffix, like 999.99 -> 1000.0
+    if formatted.trim_start_matches('-').starts_with("1000") {
+        index += 1;
+        if !has_suffix(index) {
+            return format_scientific(value, precision);
+        }
+        mantissa = value / 1000f64.powi(index - SI_UNIT_INDEX);
+        formatted = format!("{:.*}", precision, mantissa);
+    }
+    let mut formatted = trim_decimals(&formatted).to_string();
+    if formatted == "-0" {
+        formatted = String::from("0");
+    }
+    formatted.push_str(SI_SUFFIXES[index as usize]);
+    formatted
+}
+
+/// Format a number in scientific notation, such as 1.5e300.
+/// Trailing zeros of the decimals are removed.
+///
+/// # Arguments
+///
+/// * `value` - The value to format
+/// * `precision` - Maximum number of decimals
+fn format_scientific(value: f64, precision: usize) -> String {
+    let formatted = format!("{:.*e}", precision, value);
+    match formatted.split_once('e') {
+        Some((mantissa, exponent)) => format!("{}e{}", trim_decimals(mantissa), exponent),
+        None => formatted,
+    }
+}
+
+/// Format a tick with format_si, using more decimals than `precision`
+/// if that is needed to tell ticks `step` apart from each other.
+///
+/// # Arguments
+///
+/// * `value` - The tick to format
+/// * `step` - Distance between the ticks
+/// * `precision` - Minimum number of decimals
+pub fn format_tick(value: f64, step: f64, precision: usize) -> String {
+    if !step.is_finite() || step <= 0.0 {
+        return format_si(value, precision);
+    }
+    let index = si_index(value);
+    // The step in units of the number in front of the suffix, or of the exponent
+    let scaled_step = if has_suffix(index) {
+        step / 1000f64.powi(index - SI_UNIT_INDEX)
+    } else {
+        step / 10f64.powf(value.abs().log10().floor())
+    };
+    let needed = (-scaled_step.log10()).ceil().max(0.0) as usize;
+    format_si(value, precision.max(needed))
+}
+
+/// The widest a label from format_si can be with `precision` decimals,
+/// such as "-999.99k" for a precision of 2. Labels in scientific notation can be wider.
+///
+/// # Arguments
+///
+/// * `precision` - Number of decimals
+pub fn si_width(precision: usize) -> usize {
+    let decimals = if precision > 0 { precision + 1 } else { 0 };
+    // Sign, three digits, the decimals and the suffix
+    1 + 3 + decimals + 1
+}
+
+/// Format a unix timestamp as UTC time, date or both,
+/// picking the most useful one for an axis covering `span` seconds.
+///
+/// # Arguments
+///
+/// * `x` - Unix timestamp in seconds
+/// * `span` - The range of the axis, in seconds
+pub fn format_time(x: f64, span: f64) -> String {
+    let seconds = x.round() as i64;
+    let days = seconds.div_euclid(86400);
+    let time = seconds.rem_euclid(86400);
+    let (year, month, day) = civil_from_days(days);
+    let (hour, minute, second) = (time / 3600, time % 3600 / 60, time % 60);
+    if span.abs() <= 86400.0 {
+        format!("{:02}:{:02}:{:02}", hour, minute, second)
+    } else if span.abs() <= 30.0 * 86400.0 {
+        format!("{:02}-{:02} {:02}:{:02}", month, day, hour, minute)
+    } else {
+        format!("{}-{:02}-{:02}", year, month, day)
+    }
+}
+
+/// Convert a (year, month, day) date in the proleptic gregorian calendar
+/// to a number of days since 1970-01-01, the inverse of civil_from_days.
+///
+/// # Arguments
+///
+/// * `year` - The year
+/// * `month` - The month, from 1 to 12
+/// * `day` - The day of the month, from 1
+fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let yoe = year.rem_euclid(400);
+    let mp = if month > 2 { month - 3 } else { month + 9 };
+    let doy = (153 * mp + 2) / 5 + day - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    era * 146097 + doe - 719468
+}
+
+/// Convert a number of days since 1970-01-01 to a (year, month, day) date
+/// in the proleptic gregorian calendar.
+///
+/// # Arguments
+///
+/// * `days` - Days since the unix epoch
+fn civil_from_days(days: i64) -> (i64, i64, i64) {
+    let z = days + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z.rem_euclid(146097);
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+    (year, month, day)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn nice_ticks_fit_max_count() {
+        assert_eq!(nice_ticks(0.0, 10.0, 5), vec![0.0, 5.0, 10.0]);
+        assert_eq!(
+            nice_ticks(0.0, 10.0, 6),
+            vec![0.0, 2.0, 4.0, 6.0, 8.0, 10.0]
+        );
+        assert_eq!(nice_ticks(-1.0, 1.0, 3), vec![-1.0, 0.0, 1.0]);
+    }
+
+    #[test]
+    fn format_si_suffixes() {
+        assert_eq!(format_si(0.0, 1), "0");
+        assert_eq!(format_si(1500.0, 1), "1.5k");
+        assert_eq!(format_si(0.0005, 1), "500µ");
+        assert_eq!(format_si(-2e6, 2), "-2M");
+        assert_eq!(format_si(-0.00001, 1), "-10µ");
+    }
+
+    #[test]
+    fn format_si_rounds_to_next_suffix() {
+        assert_eq!(format_si(999.99, 1), "1k");
+        assert_eq!(format_si(999.99e18, 1), "1e21");
+    }
+
+    #[test]
+    fn format_si_beyond_suffixes() {
+        assert_eq!(format_si(1e300, 1), "1e300");
+        assert_eq!(format_si(-1.5e-20, 1), "-1.5e-20");
+        assert_eq!(format_si(f64::MAX, 1), "1.8e308");
+    }
+
+    #[test]
+    fn si_width_fits_widest_label() {
+        assert_eq!(si_width(2), format_si(-999.99e3, 2).len());
+        assert_eq!(si_width(0), format_si(-999e3, 0).len());
+    }
+
+    #[test]
+    fn format_tick_adds_decimals_for_step() {
+        assert_eq!(format_tick(2.5, 0.5, 0), "2.5");
+        assert_eq!(format_tick(2.05, 0.05, 0), "2.05");
+        assert_eq!(format_tick(1.5e300, 5e299, 0), "1.5e300");
+        assert_eq!(format_tick(3.0, f64::INFINITY, 0), "3");
+    }
+
+    #[test]
+    fn time_ticks_at_whole_minutes() {
+        assert_eq!(
+            time_ticks(0.0, 3600.0, 5),
+            vec![0.0, 900.0, 1800.0, 2700.0, 3600.0]
+        );
+        assert_eq!(time_ticks(10.0, 70.0, 1), vec![60.0]);
+    }
+
+    #[test]
+    fn time_ticks_at_first_day_of_month_or_year() {
+        let date = |year, month, day| days_from_civil(year, month, day) as f64 * 86400.0;
+        assert_eq!(
+            time_ticks(date(2024, 1, 15), date(2025, 1, 15), 5),
+            vec![
+                date(2024, 4, 1),
+                date(2024, 7, 1),
+                date(2024, 10, 1),
+                date(2025, 1, 1)
+            ]
+        );
+        assert_eq!(
+            time_ticks(date(2020, 6, 1), date(2030, 6, 1), 4),
+            vec![date(2025, 1, 1), date(2030, 1, 1)]
+        );
+        // A range of thousands of years still has few ticks
+        let ticks = time_ticks(0.0, 1e14, 5);
+        assert!(!ticks.is_empty() && ticks.len() <= 5);
+        assert!(ticks
+            .iter()
+            .all(|tick| format_time(*tick, 1e14).ends_with("-01-01")));
+    }
+
+    #[test]
+    fn days_from_civil_inverts_civil_from_days() {
+        for days in (-800_000..800_000).step_by(997) {
+            let (year, month, day) = civil_from_days(days);
+            assert_eq!(days_from_civil(year, month, day), days);
+        }
+        assert_eq!(format_time(1718000000.0, 3600.0), "06:13:20");
+        assert_eq!(format_time(1718000000.0, 86400.0 * 7.0), "06-10 06:13");
+        assert_eq!(format_time(1718000000.0, 86400.0 * 365.0), "2024-06-10");
+    }
+
+    #[test]
+    fn log_ticks_at_powers_of_the_base() {
+        assert_eq!(log_ticks(0.0, 3.0, 10.0, 5), vec![1.0, 10.0, 100.0, 1000.0]);
+        assert_eq!(log_ticks(0.5, 6.0, 2.0, 3), vec![2.0, 8.0, 32.0]);
+        assert_eq!(
+            log_ticks(0.0, 1000f64.log10(), 10.0, 5).last(),
+            Some(&1000.0)
+        );
+        // Less than two powers in the range, so nice values are used
+        assert_eq!(
+            log_ticks(0.0, 0.5, 10.0, 5),
+            nice_ticks(1.0, 10f64.powf(0.5), 5)
+        );
+    }
+}